@@ -0,0 +1,334 @@
+use base64::Engine;
+use directories::BaseDirs;
+use oci_distribution::secrets::RegistryAuth;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::PathBuf,
+    process::{Command, Stdio},
+    sync::{Arc, Mutex},
+};
+
+use crate::Error;
+
+/// Credentials for a registry, configured under
+/// `[package.metadata.nebula.auth."<registry>"]`.
+///
+/// Secrets are never read from `Cargo.toml` itself, only from the environment
+/// variable named by `password_env`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RegistryCredentials {
+    pub username: Option<String>,
+    /// Name of the environment variable holding the password or token.
+    pub password_env: Option<String>,
+}
+
+/// Resolves the credentials used to pull from each registry.
+///
+/// Sources are tried in order:
+/// 1. `NEBULA_AUTH_<REGISTRY>_USERNAME` and `NEBULA_AUTH_<REGISTRY>_PASSWORD`,
+///    where `<REGISTRY>` is the registry host in upper case with every other
+///    character replaced by `_`, e.g. `NEBULA_AUTH_GHCR_IO_PASSWORD`.
+/// 2. The `auth` table of the nebula metadata.
+/// 3. The Docker config (`$DOCKER_CONFIG/config.json` or
+///    `~/.docker/config.json`): `credHelpers`, then `credsStore`, then `auths`.
+///
+/// A credential helper that is missing or fails is reported as a warning and
+/// the next source is tried. Registries without credentials are accessed
+/// anonymously.
+///
+/// The credentials of a registry are resolved once and shared by clones.
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    configured: HashMap<String, RegistryCredentials>,
+    resolved: Arc<Mutex<HashMap<String, RegistryAuth>>>,
+}
+
+impl Credentials {
+    pub fn new(configured: HashMap<String, RegistryCredentials>) -> Self {
+        Self {
+            configured,
+            resolved: Default::default(),
+        }
+    }
+
+    pub fn for_registry(&self, registry: &str) -> Result<RegistryAuth, Error> {
+        // Held while resolving, so a helper runs once even for concurrent
+        // imports from the same registry.
+        let mut resolved = self
+            .resolved
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        if let Some(auth) = resolved.get(registry) {
+            return Ok(auth.clone());
+        }
+        let auth = self.resolve(registry)?;
+        resolved.insert(registry.to_string(), auth.clone());
+        Ok(auth)
+    }
+
+    fn resolve(&self, registry: &str) -> Result<RegistryAuth, Error> {
+        let env = crate::rerun::var;
+        if let Some(auth) = from_env(registry, env) {
+            return Ok(auth);
+        }
+        if let Some(credentials) = self.configured.get(registry) {
+            return from_config(registry, credentials, env);
+        }
+        match DockerConfig::load()? {
+            Some(docker) => docker.auth(registry),
+            None => Ok(RegistryAuth::Anonymous),
+        }
+    }
+}
+
+/// The name of an environment variable holding credentials for a registry.
+pub(crate) fn env_var_name(registry: &str, suffix: &str) -> String {
+    let registry: String = registry
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("NEBULA_AUTH_{registry}_{suffix}")
+}
+
+fn from_env(registry: &str, env: impl Fn(&str) -> Option<String>) -> Option<RegistryAuth> {
+    let username = env(&env_var_name(registry, "USERNAME"))?;
+    let password = env(&env_var_name(registry, "PASSWORD")).unwrap_or_default();
+    Some(RegistryAuth::Basic(username, password))
+}
+
+fn from_config(
+    registry: &str,
+    credentials: &RegistryCredentials,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<RegistryAuth, Error> {
+    let password = match &credentials.password_env {
+        Some(name) => env(name).ok_or_else(|| Error::Auth {
+            reference: registry.to_string(),
+            message: format!("environment variable `{name}` is not set"),
+        })?,
+        None => String::new(),
+    };
+    Ok(RegistryAuth::Basic(
+        credentials.username.clone().unwrap_or_default(),
+        password,
+    ))
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct DockerConfig {
+    #[serde(default)]
+    auths: HashMap<String, DockerAuth>,
+    #[serde(default)]
+    cred_helpers: HashMap<String, String>,
+    creds_store: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct DockerAuth {
+    auth: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+}
+
+/// The response of `docker-credential-<helper> get`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct HelperCredentials {
+    username: String,
+    secret: String,
+}
+
+impl DockerConfig {
+    fn path() -> Option<PathBuf> {
+        match crate::rerun::var("DOCKER_CONFIG") {
+            Some(dir) => Some(PathBuf::from(dir).join("config.json")),
+            None => {
+                crate::rerun::var("HOME");
+                BaseDirs::new().map(|dirs| dirs.home_dir().join(".docker").join("config.json"))
+            }
+        }
+    }
+
+    fn load() -> Result<Option<Self>, Error> {
+        let Some(path) = Self::path().filter(|path| path.is_file()) else {
+            return Ok(None);
+        };
+        crate::rerun::file(&path);
+        let text = std::fs::read_to_string(&path).map_err(Error::io(&path))?;
+        serde_json::from_str(&text)
+            .map(Some)
+            .map_err(|error| Error::Auth {
+                reference: path.display().to_string(),
+                message: format!("invalid Docker config: {error}"),
+            })
+    }
+
+    fn auth(&self, registry: &str) -> Result<RegistryAuth, Error> {
+        let helpers = self.cred_helpers.get(registry).into_iter();
+        for helper in helpers.chain(&self.creds_store) {
+            match run_helper(helper, registry) {
+                Ok(Some(auth)) => return Ok(auth),
+                Ok(None) => {}
+                Err(error) => throw_warning!("{error}, trying the next credentials"),
+            }
+        }
+        let entry = self
+            .auths
+            .iter()
+            .find(|(server, _)| normalize_server(server) == registry)
+            .map(|(_, entry)| entry);
+        match entry {
+            Some(entry) => entry.auth(registry),
+            None => Ok(RegistryAuth::Anonymous),
+        }
+    }
+}
+
+impl DockerAuth {
+    fn auth(&self, registry: &str) -> Result<RegistryAuth, Error> {
+        if let (Some(username), Some(password)) = (&self.username, &self.password) {
+            return Ok(RegistryAuth::Basic(username.clone(), password.clone()));
+        }
+        let Some(auth) = &self.auth else {
+            return Ok(RegistryAuth::Anonymous);
+        };
+        let invalid = || Error::Auth {
+            reference: registry.to_string(),
+            message: "invalid `auth` entry in the Docker config".to_string(),
+        };
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(auth)
+            .map_err(|_| invalid())?;
+        let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+        let (username, password) = decoded.split_once(':').ok_or_else(invalid)?;
+        Ok(RegistryAuth::Basic(
+            username.to_string(),
+            password.to_string(),
+        ))
+    }
+}
+
+/// Turns Docker config server keys such as `https://index.docker.io/v1/` into
+/// a registry host.
+fn normalize_server(server: &str) -> &str {
+    let server = server
+        .strip_prefix("https://")
+        .or_else(|| server.strip_prefix("http://"))
+        .unwrap_or(server);
+    server.split('/').next().unwrap_or(server)
+}
+
+/// Asks a Docker credential helper for credentials.
+///
+/// Returns `None` if the helper has no credentials for the registry.
+fn run_helper(helper: &str, registry: &str) -> Result<Option<RegistryAuth>, Error> {
+    let program = format!("docker-credential-{helper}");
+    let failed = |message: String| Error::Auth {
+        reference: registry.to_string(),
+        message: format!("`{program}` failed: {message}"),
+    };
+    let mut child = Command::new(&program)
+        .arg("get")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|error| failed(error.to_string()))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(registry.as_bytes())
+            .map_err(|error| failed(error.to_string()))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|error| failed(error.to_string()))?;
+    if !output.status.success() {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if stdout.contains("credentials not found") {
+            return Ok(None);
+        }
+        return Err(failed(stdout.trim().to_string()));
+    }
+    let credentials: HelperCredentials =
+        serde_json::from_slice(&output.stdout).map_err(|error| failed(error.to_string()))?;
+    Ok(Some(RegistryAuth::Basic(
+        credentials.username,
+        credentials.secret,
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_credentials() {
+        assert_eq!(
+            env_var_name("ghcr.io", "PASSWORD"),
+            "NEBULA_AUTH_GHCR_IO_PASSWORD"
+        );
+        assert_eq!(
+            env_var_name("localhost:5000", "USERNAME"),
+            "NEBULA_AUTH_LOCALHOST_5000_USERNAME"
+        );
+
+        let env = |name: &str| match name {
+            "NEBULA_AUTH_GHCR_IO_USERNAME" => Some("user".to_string()),
+            "NEBULA_AUTH_GHCR_IO_PASSWORD" => Some("token".to_string()),
+            _ => None,
+        };
+        assert_eq!(
+            from_env("ghcr.io", env),
+            Some(RegistryAuth::Basic("user".into(), "token".into()))
+        );
+        assert_eq!(from_env("docker.io", env), None);
+    }
+
+    #[test]
+    fn test_docker_config() {
+        let config: DockerConfig = serde_json::from_str(
+            r#"{
+                "auths": {
+                    "ghcr.io": { "auth": "dXNlcjp0b2tlbg==" },
+                    "https://index.docker.io/v1/": { "username": "hub", "password": "secret" }
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.auth("ghcr.io").unwrap(),
+            RegistryAuth::Basic("user".into(), "token".into())
+        );
+        assert_eq!(
+            config.auth("index.docker.io").unwrap(),
+            RegistryAuth::Basic("hub".into(), "secret".into())
+        );
+        assert_eq!(config.auth("quay.io").unwrap(), RegistryAuth::Anonymous);
+    }
+
+    #[test]
+    fn test_failing_helpers() {
+        let config: DockerConfig = serde_json::from_str(
+            r#"{
+                "auths": { "ghcr.io": { "auth": "dXNlcjp0b2tlbg==" } },
+                "credHelpers": { "ghcr.io": "nebula-missing" },
+                "credsStore": "nebula-missing"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            config.auth("ghcr.io").unwrap(),
+            RegistryAuth::Basic("user".into(), "token".into())
+        );
+        assert_eq!(config.auth("quay.io").unwrap(), RegistryAuth::Anonymous);
+    }
+}
@@ -0,0 +1,445 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::Error;
+
+const INDEX_FILE: &str = "index.toml";
+/// Taken while the index is read and written back, as builds running in
+/// parallel share the cache.
+const LOCK_FILE: &str = "index.lock";
+
+/// A content-addressed store of Wasm modules.
+///
+/// Modules are stored under `blobs/sha256/<hex>.wasm`, so the same bytes are
+/// only stored once no matter how many references point at them. The index
+/// maps references to the digests they resolved to.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    root: PathBuf,
+}
+
+/// Maps references to the manifest and Wasm digests they last resolved to.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Index {
+    #[serde(default)]
+    pub references: BTreeMap<String, IndexEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct IndexEntry {
+    /// The manifest digest the reference resolved to.
+    pub digest: String,
+    /// Hex encoded sha256 of the Wasm module, the key of its blob.
+    pub wasm_sha256: String,
+    /// Seconds since the Unix epoch when the reference first resolved to `digest`.
+    pub resolved_at: u64,
+    /// Seconds since the Unix epoch when the reference was last used.
+    pub last_used: u64,
+}
+
+/// A module in the cache, with the references that resolved to it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CachedModule {
+    /// Hex encoded sha256 of the module.
+    pub wasm_sha256: String,
+    pub path: PathBuf,
+    /// Size of the module in bytes.
+    pub size: u64,
+    /// References resolving to this module, with their index entries.
+    pub references: Vec<(String, IndexEntry)>,
+}
+
+impl CachedModule {
+    /// Seconds since the Unix epoch when any reference to the module was
+    /// last used, if any is indexed.
+    pub fn last_used(&self) -> Option<u64> {
+        self.references
+            .iter()
+            .map(|(_, entry)| entry.last_used)
+            .max()
+    }
+}
+
+/// Which modules [`Cache::prune`] removes.
+#[derive(Debug, Clone, Default)]
+pub struct Prune {
+    /// Remove modules not locked by any of these lockfiles, by Wasm sha256.
+    pub keep: Option<HashSet<String>>,
+    /// Remove modules not used for this long.
+    pub unused_for: Option<Duration>,
+}
+
+impl Cache {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn blob_path(&self, sha256: &str) -> PathBuf {
+        self.blobs_dir().join(format!("{sha256}.wasm"))
+    }
+
+    pub fn has_blob(&self, sha256: &str) -> bool {
+        self.blob_path(sha256).is_file()
+    }
+
+    /// Stores a module and returns its hex encoded sha256.
+    pub fn write_blob(&self, data: &[u8]) -> Result<String, Error> {
+        let sha256 = hex::encode(Sha256::digest(data));
+        let path = self.blob_path(&sha256);
+        if !path.is_file() {
+            write_atomic(&path, data)?;
+        }
+        Ok(sha256)
+    }
+
+    pub fn load_index(&self) -> Result<Index, Error> {
+        let path = self.root.join(INDEX_FILE);
+        if !path.try_exists().map_err(Error::io(&path))? {
+            return Ok(Index::default());
+        }
+        let text = std::fs::read_to_string(&path).map_err(Error::io(&path))?;
+        toml::from_str(&text).map_err(|source| Error::Parse { path, source })
+    }
+
+    pub fn save_index(&self, index: &Index) -> Result<(), Error> {
+        let path = self.root.join(INDEX_FILE);
+        let text = toml::to_string(index).map_err(|source| Error::Serialize {
+            path: path.clone(),
+            source,
+        })?;
+        write_atomic(&path, text.as_bytes())
+    }
+
+    /// Merges `index` into the index on disk, keeping what other builds
+    /// recorded since it was loaded.
+    pub fn merge_index(&self, index: &Index) -> Result<(), Error> {
+        let _lock = self.lock_index()?;
+        let mut merged = self.load_index()?;
+        merged.merge(index);
+        self.save_index(&merged)
+    }
+
+    /// Locks the index until the returned file is dropped.
+    fn lock_index(&self) -> Result<std::fs::File, Error> {
+        std::fs::create_dir_all(&self.root).map_err(Error::io(&self.root))?;
+        let path = self.root.join(LOCK_FILE);
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)
+            .map_err(Error::io(&path))?;
+        file.lock().map_err(Error::io(&path))?;
+        Ok(file)
+    }
+
+    fn blobs_dir(&self) -> PathBuf {
+        self.root.join("blobs").join("sha256")
+    }
+
+    /// Lists every cached module, ordered by sha256.
+    pub fn list(&self) -> Result<Vec<CachedModule>, Error> {
+        let dir = self.blobs_dir();
+        if !dir.try_exists().map_err(Error::io(&dir))? {
+            return Ok(Vec::new());
+        }
+        let index = self.load_index()?;
+        let mut modules = Vec::new();
+        for entry in std::fs::read_dir(&dir).map_err(Error::io(&dir))? {
+            let path = entry.map_err(Error::io(&dir))?.path();
+            if path.extension().is_none_or(|extension| extension != "wasm") {
+                continue;
+            }
+            let Some(wasm_sha256) = path.file_stem().and_then(|stem| stem.to_str()) else {
+                continue;
+            };
+            let size = std::fs::metadata(&path).map_err(Error::io(&path))?.len();
+            let references = index
+                .references
+                .iter()
+                .filter(|(_, entry)| entry.wasm_sha256 == wasm_sha256)
+                .map(|(reference, entry)| (reference.clone(), entry.clone()))
+                .collect();
+            modules.push(CachedModule {
+                wasm_sha256: wasm_sha256.to_string(),
+                path: path.clone(),
+                size,
+                references,
+            });
+        }
+        modules.sort_by(|a, b| a.wasm_sha256.cmp(&b.wasm_sha256));
+        Ok(modules)
+    }
+
+    /// Hashes every cached module again and returns the ones whose content
+    /// no longer matches their name.
+    pub fn verify(&self) -> Result<Vec<CachedModule>, Error> {
+        let mut corrupted = Vec::new();
+        for module in self.list()? {
+            let data = std::fs::read(&module.path).map_err(Error::io(&module.path))?;
+            if hex::encode(Sha256::digest(data)) != module.wasm_sha256 {
+                corrupted.push(module);
+            }
+        }
+        Ok(corrupted)
+    }
+
+    /// Removes the modules matching any of the criteria, together with the
+    /// references resolving to them, and returns what was removed.
+    ///
+    /// Modules without any indexed reference are never considered unused, an
+    /// index entry may be about to be written by a build running right now.
+    pub fn prune(&self, prune: &Prune) -> Result<Vec<CachedModule>, Error> {
+        let _lock = self.lock_index()?;
+        let cutoff = prune
+            .unused_for
+            .map(|unused_for| now().saturating_sub(unused_for.as_secs()));
+        let mut removed = Vec::new();
+        for module in self.list()? {
+            let unlocked = prune
+                .keep
+                .as_ref()
+                .is_some_and(|keep| !keep.contains(&module.wasm_sha256));
+            let unused =
+                cutoff.is_some_and(|cutoff| module.last_used().is_some_and(|used| used < cutoff));
+            if unlocked || unused {
+                std::fs::remove_file(&module.path).map_err(Error::io(&module.path))?;
+                removed.push(module);
+            }
+        }
+        if !removed.is_empty() {
+            let mut index = self.load_index()?;
+            index.references.retain(|_, entry| {
+                !removed
+                    .iter()
+                    .any(|module| module.wasm_sha256 == entry.wasm_sha256)
+            });
+            self.save_index(&index)?;
+        }
+        Ok(removed)
+    }
+
+    /// Removes every module and the index.
+    pub fn clear(&self) -> Result<(), Error> {
+        let blobs = self.root.join("blobs");
+        if blobs.try_exists().map_err(Error::io(&blobs))? {
+            std::fs::remove_dir_all(&blobs).map_err(Error::io(&blobs))?;
+        }
+        let index = self.root.join(INDEX_FILE);
+        if index.try_exists().map_err(Error::io(&index))? {
+            std::fs::remove_file(&index).map_err(Error::io(&index))?;
+        }
+        Ok(())
+    }
+}
+
+impl Index {
+    pub fn get(&self, reference: &str) -> Option<&IndexEntry> {
+        self.references.get(reference)
+    }
+
+    /// Adds the entries of `other`, keeping the most recently used entry of
+    /// references in both.
+    pub fn merge(&mut self, other: &Index) {
+        for (reference, entry) in &other.references {
+            match self.references.get(reference) {
+                Some(existing) if existing.last_used > entry.last_used => {}
+                _ => {
+                    self.references.insert(reference.clone(), entry.clone());
+                }
+            }
+        }
+    }
+
+    /// Records what a reference resolved to and marks it as used.
+    ///
+    /// Returns the previous manifest digest if the reference used to resolve
+    /// to something else, i.e. the tag has moved.
+    pub fn record(&mut self, reference: &str, digest: &str, wasm_sha256: &str) -> Option<String> {
+        let now = now();
+        match self.references.get_mut(reference) {
+            Some(entry) if entry.digest == digest && entry.wasm_sha256 == wasm_sha256 => {
+                entry.last_used = now;
+                None
+            }
+            _ => {
+                let entry = IndexEntry {
+                    digest: digest.to_string(),
+                    wasm_sha256: wasm_sha256.to_string(),
+                    resolved_at: now,
+                    last_used: now,
+                };
+                self.references
+                    .insert(reference.to_string(), entry)
+                    .map(|previous| previous.digest)
+                    .filter(|previous| previous != digest)
+            }
+        }
+    }
+}
+
+/// Writes a file through a temporary file, so readers never see partial content.
+///
+/// Every write gets its own temporary file, as concurrent pulls may write the
+/// same blob at the same time.
+fn write_atomic(path: &Path, data: &[u8]) -> Result<(), Error> {
+    let parent = path.parent().unwrap_or(Path::new("."));
+    std::fs::create_dir_all(parent).map_err(Error::io(parent))?;
+    let mut tmp = tempfile::NamedTempFile::new_in(parent).map_err(Error::io(parent))?;
+    tmp.write_all(data).map_err(Error::io(tmp.path()))?;
+    if let Err(error) = tmp.persist(path) {
+        // Another writer got there first with the same content.
+        if std::fs::read(path).is_ok_and(|existing| existing == data) {
+            return Ok(());
+        }
+        return Err(Error::io(path)(error.error));
+    }
+    Ok(())
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blobs_are_content_addressed() {
+        let dir = std::env::temp_dir().join(format!("nebula-cache-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+
+        let first = cache.write_blob(b"\0asm").unwrap();
+        let second = cache.write_blob(b"\0asm").unwrap();
+        assert_eq!(first, second);
+        assert!(cache.has_blob(&first));
+        assert_eq!(std::fs::read(cache.blob_path(&first)).unwrap(), b"\0asm");
+
+        let mut index = Index::default();
+        index.record("voting:latest", "sha256:a", &first);
+        cache.save_index(&index).unwrap();
+        assert_eq!(cache.load_index().unwrap(), index);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_concurrent_writes() {
+        let dir = std::env::temp_dir().join(format!("nebula-cache-race-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let data = vec![7; 1 << 20];
+        std::thread::scope(|scope| {
+            let writers: Vec<_> = (0..8)
+                .map(|_| scope.spawn(|| cache.write_blob(&data)))
+                .collect();
+            for writer in writers {
+                writer.join().unwrap().unwrap();
+            }
+        });
+        let sha256 = hex::encode(Sha256::digest(&data));
+        assert_eq!(std::fs::read(cache.blob_path(&sha256)).unwrap(), data);
+        assert_eq!(std::fs::read_dir(cache.blobs_dir()).unwrap().count(), 1);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_manage_cache() {
+        let dir = std::env::temp_dir().join(format!("nebula-cache-manage-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let locked = cache.write_blob(b"\0asm locked").unwrap();
+        let stale = cache.write_blob(b"\0asm stale").unwrap();
+        let mut index = Index::default();
+        index.record("voting:v1", "sha256:a", &locked);
+        index.record("voting:v0", "sha256:b", &stale);
+        index.references.get_mut("voting:v0").unwrap().last_used = 0;
+        cache.save_index(&index).unwrap();
+
+        let modules = cache.list().unwrap();
+        assert_eq!(modules.len(), 2);
+        assert!(modules.iter().all(|module| module.references.len() == 1));
+        assert!(cache.verify().unwrap().is_empty());
+
+        std::fs::write(cache.blob_path(&stale), b"tampered").unwrap();
+        let corrupted = cache.verify().unwrap();
+        assert_eq!(corrupted.len(), 1);
+        assert_eq!(corrupted[0].wasm_sha256, stale);
+
+        let prune = Prune {
+            unused_for: Some(Duration::from_secs(30 * 24 * 60 * 60)),
+            ..Default::default()
+        };
+        let removed = cache.prune(&prune).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!cache.has_blob(&stale));
+        assert!(cache.load_index().unwrap().get("voting:v0").is_none());
+
+        let prune = Prune {
+            keep: Some(HashSet::from([locked.clone()])),
+            ..Default::default()
+        };
+        assert!(cache.prune(&prune).unwrap().is_empty());
+
+        // Modules nobody indexed yet are not old, whatever the cutoff.
+        let unindexed = cache.write_blob(b"\0asm unindexed").unwrap();
+        let prune = Prune {
+            unused_for: Some(Duration::ZERO),
+            ..Default::default()
+        };
+        let removed = cache.prune(&prune).unwrap();
+        assert!(removed.iter().all(|module| module.wasm_sha256 != unindexed));
+        assert!(cache.has_blob(&unindexed));
+
+        cache.clear().unwrap();
+        assert!(cache.list().unwrap().is_empty());
+        assert_eq!(cache.load_index().unwrap(), Index::default());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_merge_index() {
+        let dir = std::env::temp_dir().join(format!("nebula-cache-merge-{}", std::process::id()));
+        let cache = Cache::new(&dir);
+        let mut first = Index::default();
+        first.record("voting:v1", "sha256:a", "aa");
+        let mut second = Index::default();
+        second.record("token:v1", "sha256:b", "bb");
+        second.record("voting:v1", "sha256:a", "aa");
+        second.references.get_mut("voting:v1").unwrap().last_used = 0;
+
+        cache.merge_index(&first).unwrap();
+        cache.merge_index(&second).unwrap();
+        let index = cache.load_index().unwrap();
+        assert_eq!(index.references.len(), 2);
+        assert_ne!(index.get("voting:v1").unwrap().last_used, 0);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_record_detects_moved_tags() {
+        let mut index = Index::default();
+        assert_eq!(index.record("voting:latest", "sha256:a", "aa"), None);
+        assert_eq!(index.record("voting:latest", "sha256:a", "aa"), None);
+        assert_eq!(
+            index.record("voting:latest", "sha256:b", "bb"),
+            Some("sha256:a".to_string())
+        );
+        assert_eq!(index.get("voting:latest").unwrap().wasm_sha256, "bb");
+    }
+}
@@ -0,0 +1,223 @@
+//! Rust bindings generated from the `contractspecv0` section of imported
+//! modules.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use serde::{Deserialize, Serialize};
+use soroban_spec_rust::{r#trait::generate_trait, types};
+use stellar_xdr::curr::ScSpecEntry;
+
+use crate::{Error, ResolvedModule};
+
+/// Options for the generated bindings, configured under
+/// `[package.metadata.nebula.bindings]`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Bindings {
+    /// Visibility of the generated modules, `pub(crate)` by default.
+    pub visibility: Option<String>,
+    /// Derives of the generated structs and union enums, replacing the ones
+    /// `soroban-sdk` uses, e.g. `["Debug", "Clone", "PartialEq"]`. Integer
+    /// and error enums keep theirs.
+    pub derive: Option<Vec<String>>,
+}
+
+impl Bindings {
+    /// Layers `other` over these options.
+    pub(crate) fn merge(self, other: Bindings) -> Self {
+        Bindings {
+            visibility: other.visibility.or(self.visibility),
+            derive: other.derive.or(self.derive),
+        }
+    }
+
+    fn visibility(&self) -> Result<syn::Visibility, Error> {
+        let visibility = self.visibility.as_deref().unwrap_or("pub(crate)");
+        syn::parse_str(visibility).map_err(|_| Error::InvalidBindings(visibility.to_string()))
+    }
+
+    fn derive(&self) -> Result<Option<syn::Attribute>, Error> {
+        let Some(derive) = &self.derive else {
+            return Ok(None);
+        };
+        let paths = derive
+            .iter()
+            .map(|path| {
+                syn::parse_str::<syn::Path>(path).map_err(|_| Error::InvalidBindings(path.clone()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(syn::parse_quote!(#[derive(#(#paths),*)])))
+    }
+}
+
+/// Generates the module of an import: its Wasm and provenance constants,
+/// client, types and error enums.
+///
+/// `wasm` is the path of the module relative to `OUT_DIR`, so the generated
+/// source does not depend on where the cache lives.
+pub(crate) fn contract_module(
+    name: &str,
+    spec: &[ScSpecEntry],
+    wasm: &str,
+    module: &ResolvedModule,
+    options: &Bindings,
+) -> Result<String, Error> {
+    let name =
+        syn::parse_str::<syn::Ident>(name).map_err(|_| Error::InvalidName(name.to_string()))?;
+    let visibility = options.visibility()?;
+    let derive = options.derive()?;
+    let hash: [u8; 32] = hex::decode(&module.wasm_sha256)
+        .ok()
+        .and_then(|hash| hash.try_into().ok())
+        .ok_or_else(|| Error::UnsupportedDigest {
+            reference: module.reference.clone(),
+            digest: module.wasm_sha256.clone(),
+        })?;
+    let reference = &module.reference;
+    let digest = &module.digest;
+    let (keys, values): (Vec<_>, Vec<_>) = module.annotations.iter().unzip();
+
+    let mut functions = Vec::new();
+    let mut types = Vec::new();
+    for entry in spec {
+        // Integer and error enums keep their derives, `contracttype` and
+        // `contracterror` need them to be `Copy`.
+        let item = match entry {
+            ScSpecEntry::FunctionV0(function) => {
+                functions.push(function);
+                continue;
+            }
+            ScSpecEntry::UdtStructV0(spec) => {
+                with_derive(types::generate_struct(spec), derive.as_ref())
+            }
+            ScSpecEntry::UdtUnionV0(spec) => {
+                with_derive(types::generate_union(spec), derive.as_ref())
+            }
+            ScSpecEntry::UdtEnumV0(spec) => types::generate_enum(spec),
+            ScSpecEntry::UdtErrorEnumV0(spec) => types::generate_error_enum(spec),
+        };
+        types.push(item);
+    }
+    let client = generate_trait("Contract", &functions);
+
+    let code = quote! {
+        #visibility mod #name {
+            pub const WASM: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), #wasm));
+            /// The sha256 of [`WASM`], the hash it is uploaded under.
+            pub const WASM_HASH: [u8; 32] = [#(#hash),*];
+            /// The reference the module was imported from.
+            pub const REFERENCE: &str = #reference;
+            /// The digest the reference resolved to.
+            pub const DIGEST: &str = #digest;
+            /// The annotations of the artifact, ordered by key.
+            pub const ANNOTATIONS: &[(&str, &str)] = &[#((#keys, #values)),*];
+
+            #[soroban_sdk::contractclient(name = "Client")]
+            #client
+
+            #(#types)*
+        }
+    };
+    let file = syn::parse2(code).expect("generated bindings are valid Rust");
+    Ok(prettyplease::unparse(&file))
+}
+
+/// Replaces the derives of a generated struct or union enum.
+fn with_derive(item: TokenStream, derive: Option<&syn::Attribute>) -> TokenStream {
+    let Some(derive) = derive else {
+        return item;
+    };
+    let mut item: syn::Item = syn::parse2(item).expect("generated types are valid Rust");
+    let attrs = match &mut item {
+        syn::Item::Struct(item) => &mut item.attrs,
+        syn::Item::Enum(item) => &mut item.attrs,
+        _ => return item.into_token_stream(),
+    };
+    attrs.retain(|attr| !attr.path().is_ident("derive"));
+    attrs.push(derive.clone());
+    item.into_token_stream()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support, wasm};
+    use stellar_xdr::curr::{ScSpecTypeDef, ScSpecUdtStructFieldV0, ScSpecUdtStructV0};
+
+    #[test]
+    fn test_contract_module() {
+        let mut spec = wasm::contract_spec(&test_support::module(&[(
+            wasm::SPEC_SECTION,
+            &test_support::spec(),
+        )]))
+        .unwrap();
+        spec.push(ScSpecEntry::UdtStructV0(ScSpecUdtStructV0 {
+            doc: Default::default(),
+            lib: Default::default(),
+            name: "Proposal".try_into().unwrap(),
+            fields: vec![ScSpecUdtStructFieldV0 {
+                doc: Default::default(),
+                name: "id".try_into().unwrap(),
+                type_: ScSpecTypeDef::U32,
+            }]
+            .try_into()
+            .unwrap(),
+        }));
+        let module = ResolvedModule {
+            path: "voting.wasm".into(),
+            reference: "ghcr.io/eigerco/nebula/contracts/voting:v0.2.0".into(),
+            digest: format!("sha256:{}", "0".repeat(64)),
+            wasm_sha256: format!("ff{}", "0".repeat(62)),
+            annotations: [("org.opencontainers.image.version".into(), "0.2.0".into())].into(),
+        };
+        let code = contract_module(
+            "voting",
+            &spec,
+            "/voting.wasm",
+            &module,
+            &Bindings::default(),
+        )
+        .unwrap();
+        assert!(code.starts_with("pub(crate) mod voting {"));
+        assert!(code.contains(r#"include_bytes!(concat!(env!("OUT_DIR"), "/voting.wasm"))"#));
+        assert!(code.contains("pub const WASM_HASH: [u8; 32] = ["));
+        assert!(code.contains("255u8"));
+        assert!(code.contains(
+            r#"pub const REFERENCE: &str = "ghcr.io/eigerco/nebula/contracts/voting:v0.2.0";"#
+        ));
+        assert!(code.contains(r#"("org.opencontainers.image.version", "0.2.0"),"#));
+        assert!(code.contains("fn vote(env: soroban_sdk::Env, proposal: u32) -> bool;"));
+        assert!(code.contains("#[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd)]"));
+
+        let options = Bindings {
+            visibility: Some("pub".into()),
+            derive: Some(vec!["Debug".into(), "Clone".into()]),
+        };
+        let code = contract_module("voting", &spec, "/voting.wasm", &module, &options).unwrap();
+        assert!(code.starts_with("pub mod voting {"));
+        assert!(code.contains("#[derive(Debug, Clone)]\n    pub struct Proposal"));
+
+        let options = Bindings {
+            visibility: Some("public".into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            contract_module("voting", &spec, "/voting.wasm", &module, &options),
+            Err(Error::InvalidBindings(_))
+        ));
+
+        let module = ResolvedModule {
+            wasm_sha256: "00".into(),
+            ..module
+        };
+        assert!(matches!(
+            contract_module(
+                "voting",
+                &spec,
+                "/voting.wasm",
+                &module,
+                &Bindings::default()
+            ),
+            Err(Error::UnsupportedDigest { .. })
+        ));
+    }
+}
@@ -0,0 +1,281 @@
+//! Imports of imported contracts, declared by their artifacts in the
+//! [`IMPORTS_ANNOTATION`] annotation.
+//!
+//! A contract embedding another one, like `governance` embedding `voting`,
+//! depends on the exact build it embeds. Artifacts pin their own imports by
+//! digest, so the whole graph resolves to one build of every contract, or
+//! fails when two imports need different builds of the same one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{parse_reference, Contract, Error, Layer, LockedContract, Lockfile};
+
+/// Annotation holding the imports of an artifact, a JSON object of
+/// [`DeclaredImport`]s by import name.
+pub const IMPORTS_ANNOTATION: &str = "co.eiger.nebula.imports";
+
+/// An import of a published contract, pinned by digest.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct DeclaredImport {
+    pub reference: String,
+    pub digest: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<Layer>,
+}
+
+/// The value of [`IMPORTS_ANNOTATION`] for a contract importing what
+/// `lockfile` locks.
+///
+/// Only registry imports can be declared, and only the ones of the contract
+/// itself: imports of all layers, URLs and transitive imports are left out.
+pub fn declare_imports(lockfile: &Lockfile) -> String {
+    let declared: BTreeMap<_, _> = lockfile
+        .contracts
+        .iter()
+        .filter(|locked| locked.layers.is_empty() && locked.required_by.is_empty())
+        .filter(|locked| !locked.reference.contains("://"))
+        .map(|locked| {
+            let declared = DeclaredImport {
+                reference: locked.reference.clone(),
+                digest: locked.digest.clone(),
+                layer: locked.layer.clone(),
+            };
+            (locked.name.clone(), declared)
+        })
+        .collect();
+    serde_json::to_string(&declared).expect("declared imports serialize to JSON")
+}
+
+/// The contracts resolved so far, by repository, to tell when two imports
+/// need different builds of the same contract.
+#[derive(Debug, Default)]
+pub(crate) struct Graph {
+    /// The builds of every repository, a description of the first import of
+    /// each by digest. Only direct imports use several builds.
+    repositories: HashMap<String, BTreeMap<String, String>>,
+    /// Repository of every import name, or the path or URL of direct imports
+    /// that do not come from a registry.
+    names: HashMap<String, String>,
+    /// Names of the imports of the package itself.
+    direct: HashSet<String>,
+    /// Imports whose artifacts declare each transitive import.
+    required_by: HashMap<String, BTreeSet<String>>,
+}
+
+impl Graph {
+    /// Records a module imported by the package itself, whatever its source.
+    /// Direct imports may use several builds of the same contract on
+    /// purpose, so they never conflict with each other.
+    pub fn import(&mut self, name: &str, reference: &str, digest: &str) {
+        let source = match registry_reference(reference) {
+            Some(reference) => {
+                let repository = repository(&reference);
+                self.repositories
+                    .entry(repository.clone())
+                    .or_default()
+                    .entry(digest.to_string())
+                    .or_insert_with(|| format!("import [{name}]"));
+                repository
+            }
+            None => reference.to_string(),
+        };
+        self.names.insert(name.to_string(), source);
+        self.direct.insert(name.to_string());
+    }
+
+    /// The imports declared by the artifact of `locked` that are not resolved
+    /// yet, failing if any conflicts with what is.
+    pub fn declared_by(
+        &mut self,
+        locked: &LockedContract,
+    ) -> Result<Vec<(String, Contract)>, Error> {
+        let Some(annotation) = locked.annotations.get(IMPORTS_ANNOTATION) else {
+            return Ok(Vec::new());
+        };
+        let declared: BTreeMap<String, DeclaredImport> =
+            serde_json::from_str(annotation).map_err(|source| Error::InvalidDeclaredImports {
+                reference: locked.reference.clone(),
+                source,
+            })?;
+        let mut new = Vec::new();
+        for (name, import) in declared {
+            let repository = repository(&parse_reference(&import.reference)?);
+            let description = format!("{} required by [{}]", import.digest, locked.name);
+            match self.repositories.get(&repository) {
+                Some(builds) if builds.contains_key(&import.digest) => {}
+                Some(builds) => {
+                    let first: Vec<_> = builds
+                        .iter()
+                        .map(|(digest, by)| format!("{digest} {by}"))
+                        .collect();
+                    return Err(Error::ConflictingImports {
+                        contract: repository,
+                        first: first.join(", "),
+                        second: description,
+                    });
+                }
+                None => match self.names.get(&name) {
+                    Some(other) => {
+                        return Err(Error::ConflictingImports {
+                            contract: format!("[{name}]"),
+                            first: other.clone(),
+                            second: format!("{repository} required by [{}]", locked.name),
+                        });
+                    }
+                    None => {
+                        self.repositories.insert(
+                            repository.clone(),
+                            BTreeMap::from([(import.digest.clone(), description)]),
+                        );
+                        self.names.insert(name.clone(), repository.clone());
+                        new.push((
+                            name.clone(),
+                            Contract::WithDigest {
+                                digest: Some(import.digest),
+                                reference: import.reference,
+                                layer: import.layer,
+                                all_layers: false,
+                                require: None,
+                            },
+                        ));
+                    }
+                },
+            }
+            if !self.direct.contains(&name) && self.names.get(&name) == Some(&repository) {
+                self.required_by
+                    .entry(name)
+                    .or_default()
+                    .insert(locked.name.clone());
+            }
+        }
+        Ok(new)
+    }
+
+    /// The imports requiring a transitive import, empty for direct imports.
+    pub fn required_by(&self, name: &str) -> Vec<String> {
+        self.required_by
+            .get(name)
+            .map(|required_by| required_by.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// The registry reference of a module, `None` for path and URL imports.
+fn registry_reference(reference: &str) -> Option<oci_distribution::Reference> {
+    if reference.starts_with("path+") || reference.contains("://") {
+        return None;
+    }
+    parse_reference(reference).ok()
+}
+
+fn repository(reference: &oci_distribution::Reference) -> String {
+    format!("{}/{}", reference.registry(), reference.repository())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked(name: &str, reference: &str, digest: &str) -> LockedContract {
+        LockedContract {
+            name: name.to_string(),
+            reference: reference.to_string(),
+            version: None,
+            required_by: Vec::new(),
+            digest: digest.to_string(),
+            wasm_sha256: "0".repeat(64),
+            layer: None,
+            annotations: BTreeMap::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_declared_imports() {
+        let voting = locked(
+            "voting",
+            "ghcr.io/eigerco/nebula/contracts/voting:v1",
+            "sha256:a",
+        );
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(voting.clone());
+        lockfile.insert(locked(
+            "token",
+            "https://example.com/token.wasm",
+            "sha256:b",
+        ));
+
+        let mut governance = locked("governance", "ghcr.io/eigerco/governance:v1", "sha256:c");
+        governance
+            .annotations
+            .insert(IMPORTS_ANNOTATION.to_string(), declare_imports(&lockfile));
+        let mut graph = Graph::default();
+        graph.import("governance", &governance.reference, &governance.digest);
+        let declared = graph.declared_by(&governance).unwrap();
+        assert!(matches!(
+            &declared[..],
+            [(name, Contract::WithDigest { digest: Some(digest), .. })]
+                if name == "voting" && digest == "sha256:a"
+        ));
+        assert_eq!(graph.required_by("voting"), ["governance"]);
+        assert!(graph.declared_by(&governance).unwrap().is_empty());
+
+        let mut graph = Graph::default();
+        graph.import(
+            "voting",
+            "ghcr.io/eigerco/nebula/contracts/voting:v2",
+            "sha256:d",
+        );
+        assert!(matches!(
+            graph.declared_by(&governance),
+            Err(Error::ConflictingImports { contract, .. })
+                if contract == "ghcr.io/eigerco/nebula/contracts/voting"
+        ));
+
+        // Any of the builds imported directly satisfies the declaration.
+        let mut graph = Graph::default();
+        graph.import(
+            "voting_v2",
+            "ghcr.io/eigerco/nebula/contracts/voting:v2",
+            "sha256:d",
+        );
+        graph.import(
+            "voting",
+            "ghcr.io/eigerco/nebula/contracts/voting:v1",
+            "sha256:a",
+        );
+        assert!(graph.declared_by(&governance).unwrap().is_empty());
+        assert!(graph.required_by("voting").is_empty());
+
+        let mut graph = Graph::default();
+        graph.import("voting", "ghcr.io/other/voting:v1", "sha256:a");
+        assert!(matches!(
+            graph.declared_by(&governance),
+            Err(Error::ConflictingImports { contract, .. }) if contract == "[voting]"
+        ));
+    }
+
+    #[test]
+    fn test_declared_import_named_like_a_path_import() {
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(locked(
+            "voting",
+            "ghcr.io/eigerco/nebula/contracts/voting:v1",
+            "sha256:a",
+        ));
+        let mut governance = locked("governance", "ghcr.io/eigerco/governance:v1", "sha256:c");
+        governance
+            .annotations
+            .insert(IMPORTS_ANNOTATION.to_string(), declare_imports(&lockfile));
+
+        let mut graph = Graph::default();
+        graph.import("governance", &governance.reference, &governance.digest);
+        graph.import("voting", "path+../voting/voting.wasm", "sha256:b");
+        assert!(matches!(
+            graph.declared_by(&governance),
+            Err(Error::ConflictingImports { contract, first, .. })
+                if contract == "[voting]" && first == "path+../voting/voting.wasm"
+        ));
+    }
+}
@@ -0,0 +1,276 @@
+use oci_distribution::errors::OciDistributionError;
+use std::path::PathBuf;
+
+use crate::LOCKFILE;
+
+/// Everything that can go wrong while importing contracts.
+///
+/// Messages describe the failure itself; underlying errors are available
+/// through [`std::error::Error::source`].
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("could not import contract [{name}]")]
+    Import {
+        name: String,
+        #[source]
+        source: Box<Error>,
+    },
+    /// Several imports failed, each error is an [`Error::Import`].
+    #[error("{} imports failed", .0.len())]
+    Imports(Vec<Error>),
+    #[error("invalid reference `{reference}`")]
+    InvalidReference {
+        reference: String,
+        #[source]
+        source: oci_distribution::ParseError,
+    },
+    #[error("`{0}` is not a valid Rust module name")]
+    InvalidName(String),
+    #[error("authentication to the registry failed for {reference}: {message}")]
+    Auth { reference: String, message: String },
+    #[error("registry request failed for {reference}")]
+    Network {
+        reference: String,
+        #[source]
+        source: OciDistributionError,
+    },
+    #[error("could not download {url}")]
+    Http {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("no Wasm layer found in {reference}")]
+    MissingLayer { reference: String },
+    #[error("import [{0}] sets both `require` and `all_layers`")]
+    RequireWithAllLayers(String),
+    #[error("interface does not match `require` (- required, + found):\n{}", diff.join("\n"))]
+    InterfaceMismatch { diff: Vec<String> },
+    #[error("{contract} is required both as {first} and as {second}")]
+    ConflictingImports {
+        contract: String,
+        first: String,
+        second: String,
+    },
+    #[error("invalid imports declared by {reference}")]
+    InvalidDeclaredImports {
+        reference: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("{reference} has no layer `{layer}`")]
+    LayerNotFound { reference: String, layer: String },
+    #[error("{reference} has media type `{found}`, expected `{expected}`")]
+    WrongMediaType {
+        reference: String,
+        expected: String,
+        found: String,
+    },
+    #[error("{reference} is pinned to {expected}, but resolved to {found}")]
+    DigestMismatch {
+        reference: String,
+        expected: String,
+        found: String,
+    },
+    #[error("unsupported digest `{digest}` in {reference}")]
+    UnsupportedDigest { reference: String, digest: String },
+    #[error("{reference} does not match `{LOCKFILE}`: expected {expected}, found {found}")]
+    LockMismatch {
+        reference: String,
+        expected: String,
+        found: String,
+    },
+    #[error("{} does not match `{LOCKFILE}`: expected sha256 {expected}, found {found}", path.display())]
+    VendorMismatch {
+        path: PathBuf,
+        expected: String,
+        found: String,
+    },
+    #[error("no tag of {reference} matches version `{requirement}`")]
+    NoMatchingVersion {
+        reference: String,
+        requirement: String,
+    },
+    #[error("{reference} is not a Soroban contract")]
+    NotAContract {
+        reference: String,
+        #[source]
+        source: crate::WasmError,
+    },
+    #[error("built for environment interface {found}, but soroban-sdk {sdk} only supports protocol {} and older releases", sdk.major)]
+    IncompatibleInterface {
+        found: crate::InterfaceVersion,
+        sdk: semver::Version,
+    },
+    #[error("built for environment interface {found}, a pre-release that only contracts built with the same soroban-sdk pre-release can call, not soroban-sdk {sdk}")]
+    PreReleaseInterface {
+        found: crate::InterfaceVersion,
+        sdk: semver::Version,
+    },
+    #[error("{} is not a valid contract module", path.display())]
+    InvalidModule {
+        path: PathBuf,
+        #[source]
+        source: crate::WasmError,
+    },
+    #[error("invalid bindings option `{0}`")]
+    InvalidBindings(String),
+    #[error("{reference} is not available offline")]
+    Offline { reference: String },
+    #[error("I/O error on {}", path.display())]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("invalid nebula configuration")]
+    Config(#[source] Box<figment::Error>),
+    #[error("could not parse {}", path.display())]
+    Parse {
+        path: PathBuf,
+        #[source]
+        source: toml::de::Error,
+    },
+    #[error("could not serialize {}", path.display())]
+    Serialize {
+        path: PathBuf,
+        #[source]
+        source: toml::ser::Error,
+    },
+    #[error("invalid number of parallel jobs `{0}`")]
+    InvalidJobs(String),
+    #[error("could not find a base path to cache contracts")]
+    NoCacheDir,
+    #[error("`OUT_DIR` is not set, the importer must run from a build script")]
+    NoOutDir,
+    #[error("could not start the importer runtime")]
+    Runtime(#[source] std::io::Error),
+    #[error("an import task failed")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+impl Error {
+    pub(crate) fn import(name: &str, source: Error) -> Self {
+        Error::Import {
+            name: name.to_string(),
+            source: Box::new(source),
+        }
+    }
+
+    /// The individual errors, flattening [`Error::Imports`].
+    pub fn errors(&self) -> &[Error] {
+        match self {
+            Error::Imports(errors) => errors,
+            error => std::slice::from_ref(error),
+        }
+    }
+
+    pub(crate) fn io(path: impl Into<PathBuf>) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.into();
+        move |source| Error::Io { path, source }
+    }
+
+    /// Classifies a registry error as an authentication or network failure.
+    pub(crate) fn registry(reference: impl ToString) -> impl FnOnce(OciDistributionError) -> Self {
+        let reference = reference.to_string();
+        move |source| match auth_failure(&source) {
+            Some(message) => Error::Auth { reference, message },
+            None => Error::Network { reference, source },
+        }
+    }
+
+    /// A single sentence telling the user how to fix the error.
+    pub fn hint(&self) -> String {
+        match self {
+            Error::Import { source, .. } => source.hint(),
+            Error::Imports(_) => "fix the failed imports listed above".into(),
+            Error::InvalidReference { .. } | Error::InvalidName(_) => {
+                "fix the import in `[package.metadata.nebula.imports]` in `Cargo.toml`".into()
+            }
+            Error::Auth { .. } => "check the credentials for the registry".into(),
+            Error::Network { .. } | Error::Http { .. } => {
+                "check your network connection and that the reference exists".into()
+            }
+            Error::MissingLayer { .. } | Error::WrongMediaType { .. } => {
+                "make sure the reference points to a Wasm module published with nebula-publish"
+                    .into()
+            }
+            Error::InterfaceMismatch { .. } => {
+                "import a version providing the required interface, \
+                or update `require` if the change is expected"
+                    .into()
+            }
+            Error::RequireWithAllLayers(_) => "`require` applies to a single module, import the \
+                layers you rely on separately with `layer` to check them"
+                .into(),
+            Error::ConflictingImports { .. } => "import the conflicting contract at the build its \
+                dependents were published with, or republish them against the same build"
+                .into(),
+            Error::InvalidDeclaredImports { .. } => {
+                "republish the artifact with nebula-publish, or ask its publisher to".into()
+            }
+            Error::LayerNotFound { .. } => "set `layer` to the index or the \
+                `org.opencontainers.image.title` annotation of one of the artifact's layers"
+                .into(),
+            Error::InvalidModule { .. } | Error::NotAContract { .. } => {
+                "make sure the import points to a Soroban contract built with soroban-sdk".into()
+            }
+            Error::IncompatibleInterface { .. } | Error::PreReleaseInterface { .. } => {
+                "import a version built for your soroban-sdk, \
+                or set the import to `warn` or `allow` in `[package.metadata.nebula.interface]`"
+                    .into()
+            }
+            Error::InvalidBindings(_) => {
+                "fix `[package.metadata.nebula.bindings]` in `Cargo.toml`".into()
+            }
+            Error::DigestMismatch { .. } | Error::UnsupportedDigest { .. } => {
+                "check the `digest` or `sha256` of the import in `Cargo.toml`".into()
+            }
+            Error::NoMatchingVersion { .. } => {
+                "relax the `version` of the import in `Cargo.toml` or publish a matching tag".into()
+            }
+            Error::LockMismatch { .. } => {
+                format!("the registry content changed; remove the entry from `{LOCKFILE}` to resolve it again")
+            }
+            Error::VendorMismatch { .. } => {
+                "vendor the contracts again with `nebula-importer vendor`".into()
+            }
+            Error::Offline { .. } => "build once with network access, vendor the contracts \
+                with `nebula-importer vendor`, or unset `NEBULA_OFFLINE` and `CARGO_NET_OFFLINE`"
+                .into(),
+            Error::Io { path, .. } => format!("check that {} is accessible", path.display()),
+            Error::Config(_) => {
+                "fix the `[package.metadata.nebula]` or `[workspace.metadata.nebula]` section \
+                in `Cargo.toml`"
+                    .into()
+            }
+            Error::Parse { path, .. } => format!("fix or remove {}", path.display()),
+            Error::InvalidJobs(_) => {
+                "set `jobs` in the nebula metadata or `NEBULA_JOBS` to a positive number".into()
+            }
+            Error::NoCacheDir => "set `cache` in `[package.metadata.nebula]`".into(),
+            Error::NoOutDir => "call the importer from `build.rs`".into(),
+            Error::Serialize { .. } | Error::Runtime(_) | Error::Task(_) => {
+                "this is a bug in nebula-importer, please report it".into()
+            }
+        }
+    }
+}
+
+/// What went wrong when a registry request failed for lack of valid
+/// credentials, `None` for any other failure.
+pub fn auth_failure(error: &OciDistributionError) -> Option<String> {
+    match error {
+        OciDistributionError::AuthenticationFailure(message) => Some(message.clone()),
+        OciDistributionError::UnauthorizedError { url } => {
+            Some(format!("unauthorized to access {url}"))
+        }
+        _ => None,
+    }
+}
+
+impl From<figment::Error> for Error {
+    fn from(error: figment::Error) -> Self {
+        Error::Config(Box::new(error))
+    }
+}
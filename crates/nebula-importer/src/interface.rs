@@ -0,0 +1,278 @@
+//! Compatibility of imported contracts with the Soroban environment interface
+//! targeted by the importing crate.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::Error;
+
+/// The environment interface version embedded in `contractenvmetav0`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterfaceVersion {
+    /// The ledger protocol version, the high 32 bits.
+    pub protocol: u32,
+    /// Non-zero for modules built with a pre-release SDK, the low 32 bits.
+    pub pre_release: u32,
+}
+
+impl From<u64> for InterfaceVersion {
+    fn from(version: u64) -> Self {
+        InterfaceVersion {
+            protocol: (version >> 32) as u32,
+            pre_release: version as u32,
+        }
+    }
+}
+
+impl fmt::Display for InterfaceVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pre_release {
+            0 => write!(f, "protocol {}", self.protocol),
+            pre_release => write!(f, "protocol {} pre-release {pre_release}", self.protocol),
+        }
+    }
+}
+
+/// What to do when an import targets an incompatible interface.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Allow,
+    #[default]
+    Warn,
+    Deny,
+}
+
+/// The interface check, configured under
+/// `[package.metadata.nebula.interface]`.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct InterfaceCheck {
+    /// Severity for every import without its own setting.
+    pub check: Option<Severity>,
+    /// Severity per import name.
+    #[serde(default)]
+    pub imports: HashMap<String, Severity>,
+}
+
+impl InterfaceCheck {
+    /// Layers `other` over this configuration.
+    pub(crate) fn merge(mut self, other: InterfaceCheck) -> Self {
+        self.check = other.check.or(self.check);
+        self.imports.extend(other.imports);
+        self
+    }
+
+    pub(crate) fn severity(&self, name: &str) -> Severity {
+        self.imports
+            .get(name)
+            .copied()
+            .or(self.check)
+            .unwrap_or_default()
+    }
+}
+
+/// The `soroban-sdk` version the importing crate builds with.
+#[derive(Debug, Clone)]
+pub(crate) struct Sdk {
+    pub version: semver::Version,
+}
+
+impl Sdk {
+    /// Reads the `soroban-sdk` version `package` builds with from the
+    /// `Cargo.lock` closest to `dir`, its own dependency or else the closest
+    /// one of its dependencies', as a workspace may lock several.
+    ///
+    /// Returns `None` if there is no lockfile, the package is not locked yet
+    /// or does not depend on `soroban-sdk`, in which case nothing can be
+    /// checked.
+    pub fn from_lockfile(dir: &Path, package: &str) -> Result<Option<Self>, Error> {
+        let Some(path) = find_cargo_lock(dir)? else {
+            return Ok(None);
+        };
+        crate::rerun::file(&path);
+        let text = std::fs::read_to_string(&path).map_err(Error::io(&path))?;
+        let lock: CargoLock = toml::from_str(&text).map_err(|source| Error::Parse {
+            path: path.clone(),
+            source,
+        })?;
+        Ok(lock.sdk_of(package).map(|version| Sdk { version }))
+    }
+
+    /// The ledger protocol of the SDK, which matches its major version.
+    pub fn protocol(&self) -> u32 {
+        self.version.major as u32
+    }
+
+    /// Whether modules built for `version` can be called from contracts built
+    /// with this SDK.
+    ///
+    /// Protocols are compatible with every later protocol, pre-releases only
+    /// with the exact same pre-release, which can not be told from the SDK
+    /// version, so only the protocol is compared for pre-release SDKs.
+    pub fn is_compatible(&self, version: InterfaceVersion) -> bool {
+        if version.protocol > self.protocol() {
+            return false;
+        }
+        version.pre_release == 0
+            || (!self.version.pre.is_empty() && version.protocol == self.protocol())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CargoLock {
+    #[serde(default)]
+    package: Vec<LockedPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedPackage {
+    name: String,
+    version: String,
+    /// Missing for the packages of the workspace.
+    source: Option<String>,
+    /// `name`, or `name version` when several versions are locked, followed
+    /// by ` (source)` when several sources are.
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+impl CargoLock {
+    /// The `soroban-sdk` version of the workspace package `name`, the first
+    /// one found walking its dependencies breadth first.
+    fn sdk_of(&self, name: &str) -> Option<semver::Version> {
+        let start = self
+            .package
+            .iter()
+            .position(|package| package.name == name && package.source.is_none())?;
+        let mut queue = VecDeque::from([start]);
+        let mut visited = HashSet::from([start]);
+        while let Some(index) = queue.pop_front() {
+            for dependency in &self.package[index].dependencies {
+                let Some(dependency) = self.find(dependency) else {
+                    continue;
+                };
+                let package = &self.package[dependency];
+                if package.name == "soroban-sdk" {
+                    return semver::Version::parse(&package.version).ok();
+                }
+                if visited.insert(dependency) {
+                    queue.push_back(dependency);
+                }
+            }
+        }
+        None
+    }
+
+    /// The package a `dependencies` entry refers to.
+    fn find(&self, dependency: &str) -> Option<usize> {
+        let mut parts = dependency.split(' ');
+        let name = parts.next()?;
+        let version = parts.next();
+        let source = parts
+            .next()
+            .and_then(|source| source.strip_prefix('('))
+            .and_then(|source| source.strip_suffix(')'));
+        self.package.iter().position(|package| {
+            package.name == name
+                && version.is_none_or(|version| package.version == version)
+                && source.is_none_or(|source| package.source.as_deref() == Some(source))
+        })
+    }
+}
+
+fn find_cargo_lock(dir: &Path) -> Result<Option<PathBuf>, Error> {
+    let dir = std::fs::canonicalize(dir).map_err(Error::io(dir))?;
+    Ok(dir
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|path| path.is_file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sdk(version: &str) -> Sdk {
+        Sdk {
+            version: version.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_compatibility() {
+        let version = |protocol, pre_release| InterfaceVersion {
+            protocol,
+            pre_release,
+        };
+        assert_eq!(InterfaceVersion::from((20 << 32) | 3), version(20, 3));
+
+        assert!(sdk("20.5.0").is_compatible(version(20, 0)));
+        assert!(sdk("21.0.0").is_compatible(version(20, 0)));
+        assert!(!sdk("20.5.0").is_compatible(version(21, 0)));
+        assert!(!sdk("20.5.0").is_compatible(version(20, 1)));
+        assert!(sdk("20.0.0-rc2").is_compatible(version(20, 1)));
+        assert!(!sdk("21.0.0-rc1").is_compatible(version(20, 1)));
+    }
+
+    #[test]
+    fn test_sdk_of_package() {
+        let lock: CargoLock = toml::from_str(
+            r#"
+            [[package]]
+            name = "governance"
+            version = "0.1.0"
+            dependencies = ["shared", "soroban-sdk 20.5.0"]
+
+            [[package]]
+            name = "legacy"
+            version = "0.1.0"
+            dependencies = ["soroban-sdk 21.0.0 (registry+https://github.com/rust-lang/crates.io-index)"]
+
+            [[package]]
+            name = "wrapper"
+            version = "0.1.0"
+            dependencies = ["shared"]
+
+            [[package]]
+            name = "shared"
+            version = "0.1.0"
+            dependencies = ["soroban-sdk 20.5.0"]
+
+            [[package]]
+            name = "soroban-sdk"
+            version = "20.5.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+
+            [[package]]
+            name = "soroban-sdk"
+            version = "21.0.0"
+            source = "registry+https://github.com/rust-lang/crates.io-index"
+            "#,
+        )
+        .unwrap();
+        let version = |version: &str| Some(version.parse().unwrap());
+        assert_eq!(lock.sdk_of("governance"), version("20.5.0"));
+        assert_eq!(lock.sdk_of("legacy"), version("21.0.0"));
+        assert_eq!(lock.sdk_of("wrapper"), version("20.5.0"));
+        assert_eq!(lock.sdk_of("soroban-sdk"), None);
+        assert_eq!(lock.sdk_of("missing"), None);
+    }
+
+    #[test]
+    fn test_severity() {
+        let check: InterfaceCheck = toml::from_str(
+            r#"
+            check = "deny"
+            imports = { token = "allow" }
+            "#,
+        )
+        .unwrap();
+        assert_eq!(check.severity("token"), Severity::Allow);
+        assert_eq!(check.severity("voting"), Severity::Deny);
+        assert_eq!(InterfaceCheck::default().severity("voting"), Severity::Warn);
+    }
+}
@@ -1,39 +1,305 @@
-use anyhow::Context;
+macro_rules! throw_warning {
+    ($($tokens: tt)*) => {
+        $crate::rerun::warning(&format!($($tokens)*))
+    }
+}
+
+mod auth;
+mod cache;
+mod codegen;
+mod dependencies;
+mod error;
+mod interface;
+mod lock;
+mod registries;
+mod requirements;
+mod rerun;
+mod sources;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
+mod versions;
+mod wasm;
+mod workspace;
+
 use directories::ProjectDirs;
 use figment::{
     providers::{Format, Toml},
     Figment,
 };
-use oci_distribution::{manifest, secrets::RegistryAuth, Client, Reference};
-use quote::__private::Span;
+use oci_distribution::{annotations, manifest, secrets::RegistryAuth, Client, Reference};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::{
-    collections::HashMap,
-    fs::File,
-    io::Write,
+    collections::{BTreeMap, HashMap},
+    error::Error as _,
+    fmt,
     path::{Path, PathBuf},
     sync::Arc,
 };
-use tokio::{runtime::Builder, sync::Mutex};
+use tokio::{
+    runtime::{Builder, Runtime},
+    sync::{Mutex, Semaphore},
+    task::JoinSet,
+};
+
+pub use auth::{Credentials, RegistryCredentials};
+pub use cache::{Cache, CachedModule, Index, IndexEntry, Prune};
+pub use codegen::Bindings;
+pub use dependencies::{declare_imports, DeclaredImport, IMPORTS_ANNOTATION};
+pub use error::{auth_failure, Error};
+pub use interface::{InterfaceCheck, InterfaceVersion, Severity};
+pub use lock::{LockedContract, LockedLayer, Lockfile, LOCKFILE};
+pub use registries::{RegistryConfig, MIRRORS_ENV};
+pub use requirements::Require;
+pub use wasm::{contract_metadata, ContractMetadata, WasmError};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(untagged)]
 pub enum Contract {
     NoDigest(String),
+    /// The highest tag of `reference`'s repository matching `version`.
+    Versioned {
+        reference: String,
+        version: semver::VersionReq,
+        #[serde(default)]
+        layer: Option<Layer>,
+        #[serde(default)]
+        all_layers: bool,
+        require: Option<Require>,
+    },
     WithDigest {
         digest: Option<String>,
         reference: String,
+        /// The layer to import instead of the first Wasm layer.
+        #[serde(default)]
+        layer: Option<Layer>,
+        /// Import every Wasm layer as a module of its own, overrides `layer`.
+        #[serde(default)]
+        all_layers: bool,
+        /// Functions the module has to provide, checked on every build.
+        require: Option<Require>,
+    },
+    /// A module on disk, relative to `Cargo.toml`.
+    Path {
+        path: PathBuf,
+        /// The directory `path` is relative to, the one of the manifest the
+        /// import is configured in.
+        #[serde(skip)]
+        root: PathBuf,
+        require: Option<Require>,
+    },
+    /// A module downloaded over HTTP(S), checked against `sha256` if given.
+    Url {
+        url: String,
+        sha256: Option<String>,
+        require: Option<Require>,
     },
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+impl Contract {
+    /// The interface requirements of the import, if any.
+    pub fn require(&self) -> Option<&Require> {
+        match self {
+            Contract::NoDigest(_) => None,
+            Contract::Versioned { require, .. }
+            | Contract::WithDigest { require, .. }
+            | Contract::Path { require, .. }
+            | Contract::Url { require, .. } => require.as_ref(),
+        }
+    }
+
+    fn require_mut(&mut self) -> Option<&mut Require> {
+        match self {
+            Contract::NoDigest(_) => None,
+            Contract::Versioned { require, .. }
+            | Contract::WithDigest { require, .. }
+            | Contract::Path { require, .. }
+            | Contract::Url { require, .. } => require.as_mut(),
+        }
+    }
+}
+
+/// A layer of a multi-layer artifact, by its position in the manifest or by
+/// its `org.opencontainers.image.title` annotation.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Layer {
+    Index(usize),
+    Title(String),
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layer::Index(index) => write!(f, "{index}"),
+            Layer::Title(title) => f.write_str(title),
+        }
+    }
+}
+
+/// The Wasm layers of an artifact an import uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Selection<'a> {
+    /// The first Wasm layer.
+    First,
+    One(&'a Layer),
+    /// Every Wasm layer, each as a module of its own.
+    All,
+}
+
+impl<'a> Selection<'a> {
+    fn new(layer: Option<&'a Layer>, all_layers: bool) -> Self {
+        match (layer, all_layers) {
+            (_, true) => Selection::All,
+            (Some(layer), false) => Selection::One(layer),
+            (None, false) => Selection::First,
+        }
+    }
+
+    fn layer(self) -> Option<&'a Layer> {
+        match self {
+            Selection::One(layer) => Some(layer),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Config {
+    #[serde(default)]
     imports: HashMap<String, Contract>,
     cache: Option<PathBuf>,
+    /// Credentials per registry host.
+    #[serde(default)]
+    auth: HashMap<String, RegistryCredentials>,
+    /// Directory of vendored modules, relative to `Cargo.toml`. Every
+    /// package vendors into a subdirectory named after it.
+    vendor: Option<PathBuf>,
+    /// How many imports are fetched at the same time.
+    jobs: Option<usize>,
+    /// Options for the generated bindings.
+    #[serde(default)]
+    bindings: Bindings,
+    /// How imports built for another environment interface are handled.
+    #[serde(default)]
+    interface: InterfaceCheck,
+    /// Settings per registry host or reference prefix.
+    #[serde(default)]
+    registries: BTreeMap<String, RegistryConfig>,
+    /// Generate modules for the imports declared by imported artifacts too.
+    transitive: Option<bool>,
+    /// The directory of the package the configuration was loaded for.
+    #[serde(skip)]
+    root: PathBuf,
+    /// The name of the package the configuration was loaded for.
+    #[serde(skip)]
+    package: String,
 }
-#[derive(Debug, Serialize, Deserialize, Clone)]
+
+/// Where vendored modules are looked up when `vendor` is not configured,
+/// relative to the package directory.
+pub const DEFAULT_VENDOR_DIR: &str = "vendor/nebula";
+
+/// Lists the modules `nebula-importer vendor` copied into a package's vendor
+/// directory, the only files it removes again.
+pub const VENDORED: &str = "vendored.txt";
+
+const VENDORED_HEADER: &str = "# This file is automatically @generated by nebula-importer.\n\
+                               # Modules listed here are removed once they are not imported anymore.\n";
+
+/// How many imports are fetched at the same time when `jobs` is not
+/// configured.
+pub const DEFAULT_JOBS: usize = 4;
+
+impl Config {
+    fn package_dir(&self) -> &Path {
+        match self.root.as_os_str().is_empty() {
+            true => Path::new("."),
+            false => &self.root,
+        }
+    }
+
+    /// Rejects imports combining settings that cannot apply together.
+    fn validate(&self) -> Result<(), Error> {
+        for (name, contract) in &self.imports {
+            let all_layers = matches!(
+                contract,
+                Contract::Versioned {
+                    all_layers: true,
+                    ..
+                } | Contract::WithDigest {
+                    all_layers: true,
+                    ..
+                }
+            );
+            if all_layers && contract.require().is_some() {
+                return Err(Error::RequireWithAllLayers(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// The vendor directory of this package, a subdirectory of the configured
+    /// one, which is in the package directory by default.
+    fn vendor_dir(&self) -> PathBuf {
+        self.vendor
+            .clone()
+            .unwrap_or_else(|| self.package_dir().join(DEFAULT_VENDOR_DIR))
+            .join(&self.package)
+    }
+
+    /// The parallelism limit, from `NEBULA_JOBS` or the `jobs` setting.
+    fn jobs(&self) -> Result<usize, Error> {
+        let jobs = match rerun::var("NEBULA_JOBS") {
+            Some(jobs) => jobs.parse().map_err(|_| Error::InvalidJobs(jobs))?,
+            None => self.jobs.unwrap_or(DEFAULT_JOBS),
+        };
+        if jobs == 0 {
+            return Err(Error::InvalidJobs(jobs.to_string()));
+        }
+        Ok(jobs)
+    }
+
+    /// Resolves relative paths against `root`.
+    fn rebase(mut self, root: &Path) -> Self {
+        self.cache = self.cache.map(|cache| root.join(cache));
+        self.vendor = self.vendor.map(|vendor| root.join(vendor));
+        for contract in self.imports.values_mut() {
+            if let Contract::Path { root: dir, .. } = contract {
+                *dir = root.join(&*dir);
+            }
+            if let Some(require) = contract.require_mut() {
+                require.rebase(root);
+            }
+        }
+        for registry in self.registries.values_mut() {
+            for path in &mut registry.ca_certs {
+                *path = root.join(&*path);
+            }
+        }
+        self
+    }
+
+    /// Layers package configuration over this workspace configuration:
+    /// package imports and credentials extend or replace the workspace ones
+    /// by name, and package settings win over workspace settings.
+    fn merge(mut self, package: Config) -> Self {
+        self.imports.extend(package.imports);
+        self.auth.extend(package.auth);
+        self.cache = package.cache.or(self.cache);
+        self.vendor = package.vendor.or(self.vendor);
+        self.jobs = package.jobs.or(self.jobs);
+        self.transitive = package.transitive.or(self.transitive);
+        self.bindings = self.bindings.merge(package.bindings);
+        self.interface = self.interface.merge(package.interface);
+        self.registries.extend(package.registries);
+        self
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Metadata {
+    #[serde(default)]
     nebula: Config,
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -45,161 +311,1578 @@ pub struct PackageConfig {
 pub struct Package {
     pub name: String,
     pub description: Option<String>,
+    #[serde(default)]
     pub metadata: Metadata,
 }
 
-impl Contract {
-    fn reference(&self) -> Reference {
-        match self {
-            Contract::NoDigest(reference) => reference.parse().unwrap(),
-            Contract::WithDigest { reference, .. } => reference.parse().unwrap(),
+fn parse_reference(reference: &str) -> Result<Reference, Error> {
+    reference.parse().map_err(|source| Error::InvalidReference {
+        reference: reference.to_string(),
+        source,
+    })
+}
+
+/// Imports the contracts configured in `Cargo.toml`, failing the build with
+/// a diagnostic if anything goes wrong.
+pub fn import_all_contracts() {
+    if let Err(error) = try_import_all_contracts() {
+        report_error(&error);
+        std::process::exit(1);
+    }
+}
+
+/// Imports the contracts configured in `Cargo.toml`.
+pub fn try_import_all_contracts() -> Result<(), Error> {
+    let out_dir = std::env::var("OUT_DIR").map_err(|_| Error::NoOutDir)?;
+    Importer::new().out_dir(out_dir).blocking_sync()?;
+    Ok(())
+}
+
+/// Resolves and syncs the imports of a package, the same way the build script
+/// does, from tools and tests.
+///
+/// ```no_run
+/// # async fn run() -> Result<(), nebula_importer::Error> {
+/// let resolved = nebula_importer::Importer::new()
+///     .manifest("contracts/governance/Cargo.toml")
+///     .cache("target/nebula")
+///     .sync()
+///     .await?;
+/// for (name, module) in &resolved.modules {
+///     println!("{name}: {} {}", module.digest, module.path.display());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Outside of async code, use [`Importer::blocking_sync`] instead.
+#[derive(Debug, Clone, Default)]
+pub struct Importer {
+    manifest: Option<PathBuf>,
+    cache: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+}
+
+impl Importer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The manifest of the importing package, read as given, `Cargo.toml`
+    /// in the current directory by default. `Nebula.lock` is read and
+    /// written next to it.
+    pub fn manifest(mut self, path: impl Into<PathBuf>) -> Self {
+        self.manifest = Some(path.into());
+        self
+    }
+
+    /// Caches modules in `dir` instead of the configured cache.
+    pub fn cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(dir.into());
+        self
+    }
+
+    /// Generates the bindings and copies the modules to `dir`, as the build
+    /// script does to `OUT_DIR`. Without it, imports are only resolved.
+    pub fn out_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.out_dir = Some(dir.into());
+        self
+    }
+
+    /// Resolves every import, generating the bindings if an output directory
+    /// is set, and updates `Nebula.lock` once everything succeeded.
+    pub async fn sync(&self) -> Result<Resolved, Error> {
+        let config = load_config(self.manifest.as_deref().unwrap_or(Path::new("Cargo.toml")))?;
+        let lockfile_path = config.package_dir().join(LOCKFILE);
+        rerun::file(&lockfile_path);
+        let cache = match &self.cache {
+            Some(cache) => {
+                std::fs::create_dir_all(cache).map_err(Error::io(cache))?;
+                cache.clone()
+            }
+            None => cache_dir(&config)?,
+        };
+        let lockfile = Lockfile::load(&lockfile_path)?;
+        let resolved = resolve(&config, &cache, &lockfile).await?;
+        if let Some(out_dir) = &self.out_dir {
+            generate_bindings(&config, &resolved, out_dir)?;
         }
+        if resolved.lockfile != lockfile {
+            resolved.lockfile.save(&lockfile_path)?;
+            // A lockfile created by this build is only tracked from now on.
+            rerun::file(&lockfile_path);
+        }
+        Ok(resolved)
     }
-    fn hash(&self) -> String {
-        let mut hasher = Sha256::new();
-        hasher.update(self.reference().to_string().as_bytes());
-        let bytes = hasher.finalize();
-        hex::encode(bytes)
+
+    /// [`Importer::sync`] on a runtime of its own, for build scripts and other
+    /// code that does not run on a Tokio runtime already.
+    pub fn blocking_sync(&self) -> Result<Resolved, Error> {
+        runtime()?.block_on(self.sync())
     }
 }
 
-macro_rules! throw_warning {
-    ($($tokens: tt)*) => {
-        println!("cargo:warning={}", format!($($tokens)*))
+/// Copies every import of the package of `manifest` into its subdirectory of
+/// a vendor directory, which later builds prefer over the registry. `dir`
+/// replaces the configured vendor directory.
+///
+/// Modules vendored before for imports that no longer exist are removed,
+/// other files are left alone, so packages can share a vendor directory.
+/// Imports are resolved first if needed. Returns the package's directory.
+pub fn vendor_all_contracts(manifest: &Path, dir: Option<&Path>) -> Result<PathBuf, Error> {
+    let config = load_config(manifest)?;
+    let contracts_dir = cache_dir(&config)?;
+    let lockfile_path = config.package_dir().join(LOCKFILE);
+    let lockfile = Lockfile::load(&lockfile_path)?;
+    let resolved = resolve_contracts(&config, &contracts_dir, &lockfile)?;
+    if resolved.lockfile != lockfile {
+        resolved.lockfile.save(&lockfile_path)?;
+    }
+    let dir = dir.map_or_else(|| config.vendor_dir(), |dir| dir.join(&config.package));
+    std::fs::create_dir_all(&dir).map_err(Error::io(&dir))?;
+    // Dependencies are vendored whether or not modules are generated for
+    // them, offline builds resolve them all the same.
+    let modules: BTreeMap<_, _> = resolved
+        .modules
+        .iter()
+        .chain(&resolved.dependencies)
+        .map(|(name, module)| (name.as_str(), module))
+        .collect();
+    for (name, module) in &modules {
+        let path = &module.path;
+        let vendored = dir.join(format!("{name}.wasm"));
+        // The module may already be the vendored one, copying it onto itself
+        // would truncate it.
+        let already_vendored = std::fs::canonicalize(&vendored)
+            .is_ok_and(|vendored| std::fs::canonicalize(path).is_ok_and(|path| path == vendored));
+        if !already_vendored {
+            std::fs::copy(path, &vendored).map_err(Error::io(&vendored))?;
+        }
+    }
+    // Remove the modules vendored before for imports that no longer exist.
+    let record = dir.join(VENDORED);
+    let vendored: Vec<String> = modules.keys().map(|name| format!("{name}.wasm")).collect();
+    let previous = match std::fs::read_to_string(&record) {
+        Ok(previous) => previous,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => return Err(Error::io(&record)(error)),
+    };
+    let stale = previous
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        // Only names of files in the directory, not paths out of it.
+        .filter(|file| Path::new(file).file_name() == Some(std::ffi::OsStr::new(file)))
+        .filter(|file| !vendored.iter().any(|name| name == file));
+    for file in stale {
+        let path = dir.join(file);
+        match std::fs::remove_file(&path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+                return Err(Error::io(&path)(error))
+            }
+            _ => {}
+        }
+    }
+    let mut text = VENDORED_HEADER.to_string();
+    for file in &vendored {
+        text.push_str(file);
+        text.push('\n');
     }
+    std::fs::write(&record, text).map_err(Error::io(&record))?;
+    Ok(dir)
 }
 
-pub fn import_all_contracts() {
-    println!("cargo:rerun-if-changed=Cargo.toml");
-    let PackageConfig { package } = Figment::new()
-        .merge(Toml::file("Cargo.toml"))
-        .extract()
-        .expect("Could not read config in `Cargo.toml`.");
-
-    let config = &package.metadata.nebula;
-
-    let contracts_dir = config.cache.clone().unwrap_or({
-        let project_dirs = ProjectDirs::from("co", "eiger", "nebula-importer")
-            .expect("Could not find a base path to cache contracts.");
-        project_dirs.data_local_dir().into()
-    });
-    std::fs::create_dir_all(&contracts_dir)
-        .expect("[importer] Contracts path could not be resolved");
-    sync_contracts(config, &contracts_dir).expect("[importer] Could not sync contracts.");
+/// The function signatures of a contract module, in the format `require`
+/// expects.
+pub fn module_signatures(path: &Path) -> Result<Vec<String>, Error> {
+    let wasm = std::fs::read(path).map_err(Error::io(path))?;
+    let spec = wasm::contract_spec(&wasm).map_err(|source| Error::InvalidModule {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(requirements::signatures(&spec))
 }
 
-/// Syncs contracts to a specific path
-pub fn sync_contracts(config: &Config, cache: &Path) -> anyhow::Result<()> {
-    let client = Client::new(oci_distribution::client::ClientConfig {
-        protocol: oci_distribution::client::ClientProtocol::Https,
-        ..Default::default()
-    });
-    let client = Arc::new(Mutex::new(client));
-    let runtime = Builder::new_multi_thread()
-        .worker_threads(2)
-        .enable_all()
-        .build()?;
-
-    for (name, contract) in &config.imports {
-        runtime
-            .block_on(runtime.spawn(find_and_sync_contract(
-                name.clone(),
-                contract.clone(),
-                cache.to_path_buf(),
-                client.clone(),
-            )))
-            .context(format!("Loading contract: {:?}", contract))?;
+/// Reads the nebula configuration of the package of `manifest`, layered over
+/// the configuration of its workspace.
+fn load_config(manifest: &Path) -> Result<Config, Error> {
+    let dir = match manifest.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    rerun::file(manifest);
+    // `Toml::file` looks for relative paths in the parent directories too.
+    let path = std::path::absolute(manifest).map_err(Error::io(manifest))?;
+    let PackageConfig { package } = Figment::new().merge(Toml::file(path)).extract()?;
+    let mut config = package.metadata.nebula.rebase(dir);
+    if let Some(manifest) = workspace::find_workspace_manifest(dir)? {
+        rerun::file(&manifest);
+        config = workspace::load_workspace_config(&manifest)?.merge(config);
+    }
+    config.root = dir.to_path_buf();
+    config.package = package.name;
+    config.validate()?;
+    Ok(config)
+}
+
+/// The cache of the package of `manifest`, or the default cache if there is
+/// no such manifest.
+pub fn configured_cache(manifest: &Path) -> Result<Cache, Error> {
+    let config = if manifest.is_file() {
+        load_config(manifest)?
+    } else {
+        Config::default()
+    };
+    Ok(Cache::new(cache_dir(&config)?))
+}
+
+fn cache_dir(config: &Config) -> Result<PathBuf, Error> {
+    let contracts_dir = match config.cache.clone() {
+        Some(cache) => cache,
+        None => ProjectDirs::from("co", "eiger", "nebula-importer")
+            .ok_or(Error::NoCacheDir)?
+            .data_local_dir()
+            .into(),
+    };
+    std::fs::create_dir_all(&contracts_dir).map_err(Error::io(&contracts_dir))?;
+    Ok(contracts_dir)
+}
+
+/// Whether the importer must not contact registries, set with
+/// `NEBULA_OFFLINE=1` or Cargo's `CARGO_NET_OFFLINE=true`.
+pub fn is_offline() -> bool {
+    let enabled = |name| {
+        rerun::var(name)
+            .is_some_and(|value| !matches!(value.as_str(), "" | "0" | "false" | "no" | "off"))
+    };
+    enabled("NEBULA_OFFLINE") || enabled("CARGO_NET_OFFLINE")
+}
+
+/// Renders each error as `cargo:warning` lines, one per cause, followed by a
+/// single message telling the user what to do.
+fn report_error(error: &Error) {
+    for error in error.errors() {
+        throw_warning!("[importer] {error}");
+        let mut source = error.source();
+        while let Some(cause) = source {
+            // Cargo only shows the first line of a warning.
+            for (index, line) in cause.to_string().lines().enumerate() {
+                match index {
+                    0 => throw_warning!("[importer]   caused by: {line}"),
+                    _ => throw_warning!("[importer]     {line}"),
+                }
+            }
+            source = cause.source();
+        }
+        eprintln!("error: [importer] {error}: {}", error.hint());
+    }
+}
+
+/// Syncs contracts to a specific path, pulling locked imports by digest, and
+/// generates their bindings in `OUT_DIR`.
+///
+/// Modules are copied next to the bindings, which refer to them relative to
+/// `OUT_DIR`. Returns the lockfile describing the synced imports.
+pub fn sync_contracts(
+    config: &Config,
+    cache: &Path,
+    lockfile: &Lockfile,
+) -> Result<Lockfile, Error> {
+    let out_dir = PathBuf::from(std::env::var("OUT_DIR").map_err(|_| Error::NoOutDir)?);
+    let resolved = resolve_contracts(config, cache, lockfile)?;
+    generate_bindings(config, &resolved, &out_dir)?;
+    Ok(resolved.lockfile)
+}
+
+/// Generates the bindings of the resolved modules in `out_dir`, with the
+/// modules next to them.
+fn generate_bindings(config: &Config, resolved: &Resolved, out_dir: &Path) -> Result<(), Error> {
+    let dest_path = out_dir.join("nebula_importer.rs");
+    let modules_dir = out_dir.join(MODULES_DIR);
+    std::fs::create_dir_all(&modules_dir).map_err(Error::io(&modules_dir))?;
+
+    let mut modules = BTreeMap::new();
+    for (name, imported) in resolved.generated(config) {
+        let path = &imported.path;
+        rerun::file(path);
+        let wasm = std::fs::read(path).map_err(Error::io(path))?;
+        let spec = wasm::contract_spec(&wasm).map_err(|source| {
+            Error::import(
+                name,
+                Error::InvalidModule {
+                    path: path.clone(),
+                    source,
+                },
+            )
+        })?;
+        generate_file(modules_dir.join(format!("{name}.wasm")), &wasm)?;
+        let wasm = format!("/{MODULES_DIR}/{name}.wasm");
+        let module = codegen::contract_module(name, &spec, &wasm, imported, &config.bindings)?;
+        modules.insert(name.clone(), module);
+    }
+    generate_file(&dest_path, generate_modules(&modules).as_bytes())
+}
+
+/// Checks a resolved module against the `require` of its import and the
+/// environment interface of the SDK the package builds with.
+fn check_module(
+    config: &Config,
+    name: &str,
+    module: &ResolvedModule,
+    sdk: Option<&interface::Sdk>,
+) -> Result<(), Error> {
+    let path = &module.path;
+    rerun::file(path);
+    let wasm = std::fs::read(path).map_err(Error::io(path))?;
+    let invalid = |source| Error::InvalidModule {
+        path: path.clone(),
+        source,
+    };
+    if let Some(require) = config.imports.get(name).and_then(Contract::require) {
+        require.check(&wasm::contract_spec(&wasm).map_err(invalid)?)?;
+    }
+    if let Some(sdk) = sdk {
+        let version = wasm::interface_version(&wasm).map_err(invalid)?;
+        check_interface(name, version, sdk, config.interface.severity(name))?;
+    }
+    Ok(())
+}
+
+/// Runs `pull` against the mirrors of `reference` in order, moving on to the
+/// next one when a registry cannot be reached or refuses access.
+async fn from_mirrors<T, F, Fut>(
+    context: &SyncContext,
+    name: &str,
+    reference: &Reference,
+    mut pull: F,
+) -> Result<T, Error>
+where
+    F: FnMut(Client, RegistryAuth, Reference) -> Fut,
+    Fut: std::future::Future<Output = Result<T, Error>>,
+{
+    let mut failed = None;
+    for mirror in context.mirrors.candidates(&reference.whole()) {
+        if let Some(error) = failed.take() {
+            throw_warning!("Contract [{name}] {error}, trying {mirror}");
+        }
+        let mirror = parse_reference(&mirror)?;
+        let auth = context
+            .credentials
+            .for_registry(mirror.resolve_registry())?;
+        let client = context.clients.for_registry(mirror.registry());
+        match pull(client, auth, mirror).await {
+            Err(error @ (Error::Network { .. } | Error::Auth { .. })) => failed = Some(error),
+            result => return result,
+        }
+    }
+    Err(failed.expect("every reference has at least one candidate"))
+}
+
+/// Compares the interface an import was built for with the crate's SDK.
+fn check_interface(
+    name: &str,
+    version: InterfaceVersion,
+    sdk: &interface::Sdk,
+    severity: Severity,
+) -> Result<(), Error> {
+    if severity == Severity::Allow || sdk.is_compatible(version) {
+        return Ok(());
+    }
+    let error = match version.protocol > sdk.protocol() {
+        true => Error::IncompatibleInterface {
+            found: version,
+            sdk: sdk.version.clone(),
+        },
+        false => Error::PreReleaseInterface {
+            found: version,
+            sdk: sdk.version.clone(),
+        },
+    };
+    if severity == Severity::Deny {
+        return Err(error);
     }
+    throw_warning!("Contract [{name}] {error}");
     Ok(())
 }
 
+/// Directory of `OUT_DIR` the imported modules are copied to.
+const MODULES_DIR: &str = "nebula_importer";
+
+/// The outcome of resolving every import.
+#[derive(Debug)]
+pub struct Resolved {
+    /// The Wasm module of each import, keyed by module name: the import
+    /// name, or one per layer for imports of all layers.
+    pub modules: BTreeMap<String, ResolvedModule>,
+    /// The modules of imports declared by imported artifacts, keyed by the
+    /// name they were declared with.
+    pub dependencies: BTreeMap<String, ResolvedModule>,
+    pub lockfile: Lockfile,
+}
+
+/// An imported Wasm module and where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedModule {
+    /// The module in the cache or the vendor directory.
+    pub path: PathBuf,
+    /// The reference, URL or `path+<path>` the module was imported from.
+    pub reference: String,
+    /// The manifest digest the reference resolved to, `sha256:<wasm sha256>`
+    /// for modules that do not come from a registry.
+    pub digest: String,
+    /// Hex encoded sha256 of the module.
+    pub wasm_sha256: String,
+    /// The annotations of the artifact, empty outside of registries.
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl Resolved {
+    /// The modules bindings are generated for: every import, and the
+    /// declared imports if `transitive` is set.
+    fn generated<'a>(
+        &'a self,
+        config: &Config,
+    ) -> impl Iterator<Item = (&'a String, &'a ResolvedModule)> {
+        let dependencies = match config.transitive {
+            Some(true) => self.dependencies.iter(),
+            _ => Default::default(),
+        };
+        self.modules.iter().chain(dependencies)
+    }
+}
+
+impl ResolvedModule {
+    fn locked(locked: &LockedContract, wasm_sha256: &str, path: PathBuf) -> Self {
+        ResolvedModule {
+            path,
+            reference: locked.reference.clone(),
+            digest: locked.digest.clone(),
+            wasm_sha256: wasm_sha256.to_string(),
+            annotations: locked.annotations.clone(),
+        }
+    }
+}
+
+/// State shared by the tasks syncing each import.
+pub(crate) struct SyncContext {
+    pub cache: Cache,
+    pub index: Mutex<Index>,
+    /// Cloned by every task, clones share the connection pool.
+    pub clients: registries::Clients,
+    /// Limits how many imports are synced at the same time.
+    pub jobs: Semaphore,
+    pub credentials: Credentials,
+    pub mirrors: registries::Mirrors,
+    pub offline: bool,
+    pub vendor: PathBuf,
+}
+
+impl SyncContext {
+    /// The vendored module of a locked import, if there is one.
+    pub fn vendored(&self, name: &str, wasm_sha256: &str) -> Result<Option<PathBuf>, Error> {
+        // Vendoring a module later has to rerun the build.
+        rerun::file(&self.vendor);
+        let vendored = self.vendor.join(format!("{name}.wasm"));
+        if !vendored.is_file() {
+            return Ok(None);
+        }
+        let data = std::fs::read(&vendored).map_err(Error::io(&vendored))?;
+        let found = hex::encode(Sha256::digest(data));
+        if found != wasm_sha256 {
+            return Err(Error::VendorMismatch {
+                path: vendored,
+                expected: wasm_sha256.to_string(),
+                found,
+            });
+        }
+        Ok(Some(vendored))
+    }
+}
+
+/// Makes sure every import is available locally, without generating code.
+///
+/// Imports are synced concurrently, at most `jobs` at a time. Every import is
+/// attempted, and all failures are reported together.
+///
+/// This starts a runtime of its own and must not be called from async code,
+/// use [`Importer::sync`] there.
+pub fn resolve_contracts(
+    config: &Config,
+    cache: &Path,
+    lockfile: &Lockfile,
+) -> Result<Resolved, Error> {
+    runtime()?.block_on(resolve(config, cache, lockfile))
+}
+
+/// The runtime the blocking entry points run imports on.
+fn runtime() -> Result<Runtime, Error> {
+    Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .map_err(Error::Runtime)
+}
+
+async fn resolve(config: &Config, cache: &Path, lockfile: &Lockfile) -> Result<Resolved, Error> {
+    config.validate()?;
+    let clients = registries::Clients::new(&config.registries)?;
+    let cache = Cache::new(cache);
+    let context = Arc::new(SyncContext {
+        index: Mutex::new(cache.load_index()?),
+        cache,
+        clients,
+        jobs: Semaphore::new(config.jobs()?),
+        credentials: Credentials::new(config.auth.clone()),
+        mirrors: registries::Mirrors::new(&config.registries, rerun::var(MIRRORS_ENV).as_deref()),
+        offline: is_offline(),
+        vendor: config.vendor_dir(),
+    });
+    // Modules are keyed by import name so the generated file does not depend
+    // on the iteration order of the config or the order tasks finish in.
+    let mut modules = BTreeMap::new();
+    let mut dependencies = BTreeMap::new();
+    let mut updated = Lockfile::default();
+    let mut errors = Vec::new();
+    let mut graph = dependencies::Graph::default();
+    // The configured imports come first, then the imports their artifacts
+    // declare, round after round until no artifact declares anything new.
+    let mut pending: Vec<_> = config.imports.clone().into_iter().collect();
+    let mut direct = true;
+    while !pending.is_empty() {
+        let mut tasks = JoinSet::new();
+        for (name, contract) in pending.drain(..) {
+            let context = context.clone();
+            let locked = lockfile.get(&name).cloned();
+            tasks.spawn(async move {
+                let _permit = context
+                    .jobs
+                    .acquire()
+                    .await
+                    .expect("semaphore is never closed");
+                find_and_sync_contract(name.clone(), contract, context.clone(), locked)
+                    .await
+                    .map_err(|error| Error::import(&name, error))
+            });
+        }
+        let mut locked = Vec::new();
+        while let Some(result) = tasks.join_next().await {
+            match result.map_err(Error::from).and_then(|result| result) {
+                Ok((synced, entry)) => {
+                    if direct {
+                        modules.extend(synced);
+                    } else {
+                        dependencies.extend(synced);
+                    }
+                    locked.extend(entry);
+                }
+                Err(error) => errors.push(error),
+            }
+        }
+        if direct {
+            for (name, module) in &modules {
+                graph.import(name, &module.reference, &module.digest);
+            }
+        }
+        for entry in locked {
+            match graph.declared_by(&entry) {
+                Ok(declared) => pending.extend(declared),
+                Err(error) => errors.push(Error::import(&entry.name, error)),
+            }
+            updated.insert(entry);
+        }
+        direct = false;
+    }
+    for entry in &mut updated.contracts {
+        entry.required_by = graph.required_by(&entry.name);
+    }
+    // Whatever was synced is indexed, even if other imports failed.
+    context.cache.merge_index(&*context.index.lock().await)?;
+    let resolved = Resolved {
+        modules,
+        dependencies,
+        lockfile: updated,
+    };
+    let sdk = interface::Sdk::from_lockfile(config.package_dir(), &config.package)?;
+    for (name, module) in resolved.generated(config) {
+        if let Err(error) = check_module(config, name, module, sdk.as_ref()) {
+            errors.push(Error::import(name, error));
+        }
+    }
+    if errors.len() > 1 {
+        errors.sort_by_key(ToString::to_string);
+        return Err(Error::Imports(errors));
+    }
+    if let Some(error) = errors.pop() {
+        return Err(error);
+    }
+    Ok(resolved)
+}
+
+/// Makes sure the contract is available locally and returns its modules by
+/// module name, together with its lockfile entry if the import kind is
+/// locked.
 async fn find_and_sync_contract(
     name: String,
     contract: Contract,
-    mut path: PathBuf,
-    client: Arc<Mutex<Client>>,
-) {
-    let out_dir = std::env::var("OUT_DIR").unwrap();
-    let dest_path = Path::new(&out_dir).join("nebula_importer.rs");
-
-    path.push(format!("{name}_{}.wasm", contract.hash()));
-    match path.try_exists() {
-        Ok(true) => {
-            let path_str = path.to_str().unwrap().to_string();
-            let name = syn::Ident::new(&name, Span::call_site());
-
-            let code = quote::quote! {
-                pub (crate) mod #name {
-                    soroban_sdk::contractimport!(file = #path_str);
+    context: Arc<SyncContext>,
+    locked: Option<LockedContract>,
+) -> Result<(Vec<(String, ResolvedModule)>, Option<LockedContract>), Error> {
+    let (reference, digest, version, selection) = match &contract {
+        Contract::NoDigest(reference) => (reference, None, None, Selection::First),
+        Contract::Versioned {
+            reference,
+            version,
+            layer,
+            all_layers,
+            ..
+        } => (
+            reference,
+            None,
+            Some(version),
+            Selection::new(layer.as_ref(), *all_layers),
+        ),
+        Contract::WithDigest {
+            digest,
+            reference,
+            layer,
+            all_layers,
+            ..
+        } => (
+            reference,
+            digest.as_deref(),
+            None,
+            Selection::new(layer.as_ref(), *all_layers),
+        ),
+        Contract::Path { path, root, .. } => {
+            let module = sources::sync_path(path, root, &context).await?;
+            return Ok((vec![(name.clone(), module)], None));
+        }
+        Contract::Url { url, sha256, .. } => {
+            let (path, locked) =
+                sources::sync_url(&name, url, sha256.as_deref(), &context, locked).await?;
+            let module = ResolvedModule::locked(&locked, &locked.wasm_sha256, path);
+            return Ok((vec![(name.clone(), module)], Some(locked)));
+        }
+    };
+    let (modules, locked) = sync_oci(
+        &name, reference, digest, version, selection, &context, locked,
+    )
+    .await?;
+    Ok((modules, Some(locked)))
+}
+
+/// Makes sure an OCI import is in the cache.
+///
+/// A lockfile entry is only honoured while the configured reference (and
+/// digest, if any) and layer selection are unchanged and its locked tag still
+/// satisfies the `version` requirement, otherwise the reference is resolved
+/// again. Vendored modules matching the lockfile are preferred over the cache.
+async fn sync_oci(
+    name: &str,
+    reference_str: &str,
+    digest: Option<&str>,
+    version: Option<&semver::VersionReq>,
+    selection: Selection<'_>,
+    context: &SyncContext,
+    locked: Option<LockedContract>,
+) -> Result<(Vec<(String, ResolvedModule)>, LockedContract), Error> {
+    let SyncContext { cache, index, .. } = context;
+    let reference = parse_reference(reference_str)?;
+    let is_locked_by = |locked: &LockedContract| {
+        locked.reference == reference_str
+            && digest.is_none_or(|digest| digest == locked.digest)
+            && version.is_none_or(|requirement| {
+                locked
+                    .version
+                    .as_deref()
+                    .and_then(versions::tag_version)
+                    .is_some_and(|version| requirement.matches(&version))
+            })
+            && locked.layer.as_ref() == selection.layer()
+            && locked.layers.is_empty() != (selection == Selection::All)
+    };
+    let locked = match locked.filter(is_locked_by) {
+        Some(locked) => {
+            let modules = locked.modules();
+            let mut vendored = Vec::new();
+            for module in &modules {
+                match context.vendored(&module.name, &module.wasm_sha256)? {
+                    Some(path) => vendored.push((
+                        module.name.clone(),
+                        ResolvedModule::locked(&locked, &module.wasm_sha256, path),
+                    )),
+                    None => break,
+                }
+            }
+            if vendored.len() == modules.len() {
+                return Ok((vendored, locked));
+            }
+            let pinned = Reference::with_digest(
+                reference.registry().to_string(),
+                reference.repository().to_string(),
+                locked.digest.clone(),
+            );
+            if modules
+                .iter()
+                .any(|module| !cache.has_blob(&module.wasm_sha256))
+            {
+                if context.offline {
+                    return Err(Error::Offline {
+                        reference: pinned.whole(),
+                    });
+                }
+                throw_warning!("Contract [{name}] could not be found in cache, fetching...");
+                let pulled = from_mirrors(
+                    context,
+                    name,
+                    &pinned,
+                    |mut client, auth, mirror| async move {
+                        pull_wasm(&mut client, &auth, &mirror, cache, selection).await
+                    },
+                )
+                .await?;
+                let found = pulled.modules(name, selection);
+                if pulled.digest != locked.digest || found != modules {
+                    return Err(Error::LockMismatch {
+                        reference: reference_str.to_string(),
+                        expected: describe_modules(&locked.digest, &modules),
+                        found: describe_modules(&pulled.digest, &found),
+                    });
+                }
+                throw_warning!("Contract [{name}] fetched {}", locked.digest);
+            }
+            let mut index = index.lock().await;
+            for module in &modules {
+                index.record(
+                    &index_key(&pinned.whole(), selection, &module.name),
+                    &locked.digest,
+                    &module.wasm_sha256,
+                );
+            }
+            locked
+        }
+        None if context.offline && (version.is_some() || selection != Selection::First) => {
+            return Err(Error::Offline {
+                reference: reference_str.to_string(),
+            });
+        }
+        None if context.offline => {
+            // Without a registry the best we can do is what the reference
+            // resolved to the last time it was pulled.
+            let entry = index
+                .lock()
+                .await
+                .get(reference_str)
+                .filter(|entry| digest.is_none_or(|digest| digest == entry.digest))
+                .filter(|entry| cache.has_blob(&entry.wasm_sha256))
+                .cloned()
+                .ok_or_else(|| Error::Offline {
+                    reference: reference_str.to_string(),
+                })?;
+            throw_warning!(
+                "Contract [{name}] is not locked, using cached {} while offline",
+                entry.digest
+            );
+            LockedContract {
+                name: name.to_string(),
+                reference: reference_str.to_string(),
+                version: None,
+                required_by: Vec::new(),
+                digest: entry.digest,
+                wasm_sha256: entry.wasm_sha256,
+                layer: None,
+                annotations: BTreeMap::new(),
+                layers: Vec::new(),
+            }
+        }
+        None => {
+            throw_warning!("Contract [{name}] is not locked, resolving {reference}...");
+            let (tag, pulled) = from_mirrors(
+                context,
+                name,
+                &reference,
+                |mut client, auth, mirror| async move {
+                    let tag = match version {
+                        Some(requirement) => Some(
+                            versions::resolve_tag(&mut client, &auth, &mirror, requirement).await?,
+                        ),
+                        None => None,
+                    };
+                    let target = match (digest, &tag) {
+                        (Some(digest), _) => Reference::with_digest(
+                            mirror.registry().to_string(),
+                            mirror.repository().to_string(),
+                            digest.to_string(),
+                        ),
+                        (None, Some(tag)) => Reference::with_tag(
+                            mirror.registry().to_string(),
+                            mirror.repository().to_string(),
+                            tag.clone(),
+                        ),
+                        (None, None) => mirror,
+                    };
+                    let pulled = pull_wasm(&mut client, &auth, &target, cache, selection).await?;
+                    Ok((tag, pulled))
+                },
+            )
+            .await?;
+            let reference = match (&tag, version) {
+                (Some(tag), Some(requirement)) => {
+                    throw_warning!("Contract [{name}] version {requirement} resolved to {tag}");
+                    Reference::with_tag(
+                        reference.registry().to_string(),
+                        reference.repository().to_string(),
+                        tag.clone(),
+                    )
                 }
+                _ => reference,
             };
-            generate_file(&dest_path, code.to_string().as_bytes());
-        }
-        Ok(false) => {
-            throw_warning!("Contract [{name}] could not be found in cache, fetching...");
-            let mut client = client.lock().await;
-            let reference = contract.reference();
-            pull_wasm(&mut client, &RegistryAuth::Anonymous, &reference, &path).await;
-            let path_str = path.to_str().unwrap().to_string();
-            let name = syn::Ident::new(&name, Span::call_site());
-
-            let code = quote::quote! {
-                pub (crate) mod #name {
-                    soroban_sdk::contractimport!(file = #path_str);
+            if let Some(digest) = digest {
+                if pulled.digest != digest {
+                    return Err(Error::DigestMismatch {
+                        reference: reference_str.to_string(),
+                        expected: digest.to_string(),
+                        found: pulled.digest,
+                    });
                 }
+            }
+            let indexed = match tag {
+                Some(_) => reference.whole(),
+                None => reference_str.to_string(),
             };
-            generate_file(&dest_path, code.to_string().as_bytes());
+            let modules = pulled.modules(name, selection);
+            let mut previous = None;
+            {
+                let mut index = index.lock().await;
+                for module in &modules {
+                    let moved = index.record(
+                        &index_key(&indexed, selection, &module.name),
+                        &pulled.digest,
+                        &module.wasm_sha256,
+                    );
+                    previous = previous.or(moved);
+                }
+            }
+            throw_warning!("Contract [{name}] fetched {}", pulled.digest);
+            if let Some(previous) = previous {
+                throw_warning!(
+                    "Contract [{name}] {reference} moved from {previous} to {}",
+                    pulled.digest
+                );
+            }
+            LockedContract {
+                name: name.to_string(),
+                reference: reference_str.to_string(),
+                version: tag,
+                required_by: Vec::new(),
+                digest: pulled.digest,
+                wasm_sha256: modules[0].wasm_sha256.clone(),
+                layer: selection.layer().cloned(),
+                annotations: pulled.annotations,
+                layers: match selection {
+                    Selection::All => modules,
+                    _ => Vec::new(),
+                },
+            }
         }
-        Err(e) => throw_warning!("{e:?}"),
     };
+    let modules = locked
+        .modules()
+        .into_iter()
+        .map(|module| {
+            let path = cache.blob_path(&module.wasm_sha256);
+            let resolved = ResolvedModule::locked(&locked, &module.wasm_sha256, path);
+            (module.name, resolved)
+        })
+        .collect();
+    Ok((modules, locked))
+}
+
+/// The index key of a module of `reference`. Modules of layers other than
+/// the first Wasm layer are keyed by what selected them, so an offline build
+/// never mistakes one layer for another.
+fn index_key(reference: &str, selection: Selection<'_>, module: &str) -> String {
+    match selection {
+        Selection::First => reference.to_string(),
+        Selection::One(layer) => format!("{reference}#{layer}"),
+        Selection::All => format!("{reference}#{module}"),
+    }
+}
+
+fn describe_modules(digest: &str, modules: &[LockedLayer]) -> String {
+    let sha256s: Vec<_> = modules
+        .iter()
+        .map(|module| module.wasm_sha256.as_str())
+        .collect();
+    format!("manifest {digest} with Wasm sha256 {}", sha256s.join(", "))
+}
+
+/// Joins the modules of all imports, ordered by module name.
+fn generate_modules(modules: &BTreeMap<String, String>) -> String {
+    modules
+        .values()
+        .map(String::as_str)
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-pub(crate) async fn pull_wasm(
+/// Wasm modules pulled from a registry into the cache.
+pub(crate) struct PulledWasm {
+    /// The digest of the manifest the modules were pulled from.
+    pub digest: String,
+    /// The selected Wasm layers, in manifest order.
+    pub layers: Vec<PulledLayer>,
+    /// The annotations of the manifest.
+    pub annotations: BTreeMap<String, String>,
+}
+
+pub(crate) struct PulledLayer {
+    /// Position of the layer in the manifest.
+    pub index: usize,
+    /// The `org.opencontainers.image.title` annotation of the layer.
+    pub title: Option<String>,
+    /// Hex encoded sha256 of the module, the key of its blob in the cache.
+    pub wasm_sha256: String,
+}
+
+impl PulledWasm {
+    /// Names the modules after the import, and after their layer as well
+    /// when importing every layer.
+    fn modules(&self, name: &str, selection: Selection<'_>) -> Vec<LockedLayer> {
+        self.layers
+            .iter()
+            .map(|layer| LockedLayer {
+                name: match selection {
+                    Selection::All => layer_module(name, layer.index, layer.title.as_deref()),
+                    _ => name.to_string(),
+                },
+                wasm_sha256: layer.wasm_sha256.clone(),
+            })
+            .collect()
+    }
+}
+
+/// The module name of a layer: the import name followed by the file stem of
+/// the layer title, e.g. `bundle_voting` for `voting.wasm`, or by the index
+/// of untitled layers.
+fn layer_module(name: &str, index: usize, title: Option<&str>) -> String {
+    let suffix = title
+        .and_then(|title| Path::new(title).file_stem())
+        .map(|stem| {
+            stem.to_string_lossy()
+                .chars()
+                .map(|c| match c {
+                    c if c.is_ascii_alphanumeric() => c.to_ascii_lowercase(),
+                    _ => '_',
+                })
+                .collect()
+        })
+        .unwrap_or_else(|| index.to_string());
+    format!("{name}_{suffix}")
+}
+
+/// Pulls the selected Wasm layers of a manifest into the cache.
+///
+/// The manifest digest is checked against the reference when pulling by
+/// digest, and each layer is checked against the digest listed in the
+/// manifest, so nothing is stored that does not match what was asked for.
+/// Layers that are already in the cache are not downloaded again.
+async fn pull_wasm(
     client: &mut Client,
     auth: &RegistryAuth,
     reference: &Reference,
-    output: &PathBuf,
-) {
-    let image_content = client
-        .pull(reference, auth, vec![manifest::WASM_LAYER_MEDIA_TYPE])
-        .await
-        .unwrap_or_else(|_| panic!("Cannot pull Wasm module from {}",
-            reference))
-        .layers
-        .into_iter()
-        .next()
-        .map(|layer| layer.data)
-        .expect("No data found");
-    tokio::fs::write(output, image_content)
+    cache: &Cache,
+    selection: Selection<'_>,
+) -> Result<PulledWasm, Error> {
+    let (image_manifest, digest) = client
+        .pull_image_manifest(reference, auth)
         .await
-        .expect("Cannot write to file");
+        .map_err(Error::registry(reference))?;
+    if let Some(expected) = reference.digest() {
+        if digest != expected {
+            return Err(Error::DigestMismatch {
+                reference: reference.whole(),
+                expected: expected.to_string(),
+                found: digest,
+            });
+        }
+    }
+    let mut layers = Vec::new();
+    for (index, layer) in wasm_layers(&reference.whole(), &image_manifest, selection)? {
+        let Some(wasm_sha256) = layer.digest.strip_prefix("sha256:") else {
+            return Err(Error::UnsupportedDigest {
+                reference: reference.whole(),
+                digest: layer.digest.clone(),
+            });
+        };
+        if !cache.has_blob(wasm_sha256) {
+            let mut data = Vec::new();
+            client
+                .pull_blob(reference, &layer.digest, &mut data)
+                .await
+                .map_err(Error::registry(reference))?;
+            verify_layer(&reference.whole(), &data, &layer.digest)?;
+            validate_contract(&reference.whole(), &data)?;
+            cache.write_blob(&data)?;
+        }
+        layers.push(PulledLayer {
+            index,
+            title: layer_title(layer).map(str::to_string),
+            wasm_sha256: wasm_sha256.to_string(),
+        });
+    }
+    Ok(PulledWasm {
+        digest,
+        layers,
+        annotations: image_manifest
+            .annotations
+            .unwrap_or_default()
+            .into_iter()
+            .collect(),
+    })
+}
+
+/// Finds the selected Wasm layers of a manifest with their positions, after
+/// checking that the manifest describes a Wasm artifact rather than e.g. a
+/// container image.
+fn wasm_layers<'a>(
+    reference: &str,
+    image_manifest: &'a manifest::OciImageManifest,
+    selection: Selection<'_>,
+) -> Result<Vec<(usize, &'a manifest::OciDescriptor)>, Error> {
+    let wrong_media_type = |expected: &str, found: &str| Error::WrongMediaType {
+        reference: reference.to_string(),
+        expected: expected.to_string(),
+        found: found.to_string(),
+    };
+    if image_manifest.config.media_type != manifest::WASM_CONFIG_MEDIA_TYPE {
+        return Err(wrong_media_type(
+            manifest::WASM_CONFIG_MEDIA_TYPE,
+            &image_manifest.config.media_type,
+        ));
+    }
+    let mut layers = image_manifest.layers.iter().enumerate();
+    let is_wasm = |(_, layer): &(usize, &manifest::OciDescriptor)| {
+        layer.media_type == manifest::WASM_LAYER_MEDIA_TYPE
+    };
+    let selected: Vec<_> = match selection {
+        Selection::First => layers.find(is_wasm).into_iter().collect(),
+        Selection::All => layers.filter(is_wasm).collect(),
+        Selection::One(wanted) => {
+            let found = layers
+                .find(|(index, layer)| match wanted {
+                    Layer::Index(wanted) => index == wanted,
+                    Layer::Title(title) => layer_title(layer) == Some(title.as_str()),
+                })
+                .ok_or_else(|| Error::LayerNotFound {
+                    reference: reference.to_string(),
+                    layer: wanted.to_string(),
+                })?;
+            if !is_wasm(&found) {
+                return Err(wrong_media_type(
+                    manifest::WASM_LAYER_MEDIA_TYPE,
+                    &found.1.media_type,
+                ));
+            }
+            vec![found]
+        }
+    };
+    match (selected.is_empty(), image_manifest.layers.first()) {
+        (false, _) => Ok(selected),
+        (true, Some(layer)) => Err(wrong_media_type(
+            manifest::WASM_LAYER_MEDIA_TYPE,
+            &layer.media_type,
+        )),
+        (true, None) => Err(Error::MissingLayer {
+            reference: reference.to_string(),
+        }),
+    }
+}
+
+fn layer_title(layer: &manifest::OciDescriptor) -> Option<&str> {
+    layer
+        .annotations
+        .as_ref()?
+        .get(annotations::ORG_OPENCONTAINERS_IMAGE_TITLE)
+        .map(String::as_str)
+}
+
+/// Checks that a module is a Soroban contract before it enters the cache.
+pub(crate) fn validate_contract(reference: &str, data: &[u8]) -> Result<(), Error> {
+    wasm::validate_contract(data).map_err(|source| Error::NotAContract {
+        reference: reference.to_string(),
+        source,
+    })
+}
+
+/// Checks the content of a layer against its `sha256:<hex>` digest.
+fn verify_layer(reference: &str, data: &[u8], digest: &str) -> Result<(), Error> {
+    let actual = format!("sha256:{}", hex::encode(Sha256::digest(data)));
+    if actual != digest {
+        return Err(Error::DigestMismatch {
+            reference: reference.to_string(),
+            expected: digest.to_string(),
+            found: actual,
+        });
+    }
+    Ok(())
 }
 
-fn generate_file<P: AsRef<Path>>(path: P, text: &[u8]) {
-    let mut f = File::create(path).unwrap();
-    f.write_all(text).unwrap()
+fn generate_file<P: AsRef<Path>>(path: P, text: &[u8]) -> Result<(), Error> {
+    let path = path.as_ref();
+    std::fs::write(path, text).map_err(Error::io(path))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use figment;
 
     #[test]
+    #[allow(clippy::result_large_err)]
     fn test_config() {
         figment::Jail::expect_with(|jail| {
             jail.create_file(
                 "Cargo.toml",
                 r#"
+                [package]
+                name = "test"
+
                 [package.metadata.nebula.imports]
                 token = "ghcr.io/eigerco/nebula/contracts/token"
             "#,
             )?;
-            let config: Config = Figment::new()
+            let PackageConfig { package } = Figment::new()
                 .merge(Toml::file("Cargo.toml"))
                 .extract()
                 .unwrap();
+            assert!(package.metadata.nebula.imports.contains_key("token"));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn test_workspace_config() {
+        figment::Jail::expect_with(|jail| {
+            jail.create_file(
+                "Cargo.toml",
+                r#"
+                [workspace]
+                members = ["governance"]
+
+                [workspace.metadata.nebula]
+                cache = "cache"
+
+                [workspace.metadata.nebula.imports]
+                token = "ghcr.io/eigerco/nebula/contracts/token:v0.1.0"
+                voting = "ghcr.io/eigerco/nebula/contracts/voting:v0.1.0"
+            "#,
+            )?;
+            jail.create_dir("governance")?;
+            jail.create_file(
+                "governance/Cargo.toml",
+                r#"
+                [package]
+                name = "governance"
+
+                [package.metadata.nebula.imports]
+                voting = "ghcr.io/eigerco/nebula/contracts/voting:v0.2.0"
+                raffle = { path = "raffle.wasm" }
+            "#,
+            )?;
+            let root = std::fs::canonicalize(jail.directory()).unwrap();
+            let config = load_config(&root.join("governance/Cargo.toml")).unwrap();
+
+            assert_eq!(config.imports.len(), 3);
+            assert!(matches!(
+                &config.imports["voting"],
+                Contract::NoDigest(reference) if reference.ends_with("v0.2.0")
+            ));
+            assert!(matches!(
+                &config.imports["raffle"],
+                Contract::Path { path, root: dir, .. }
+                    if path == Path::new("raffle.wasm") && *dir == root.join("governance")
+            ));
+            assert_eq!(config.cache, Some(root.join("cache")));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn test_importer() {
+        figment::Jail::expect_with(|jail| {
+            jail.create_dir("governance")?;
+            jail.create_file(
+                "governance/Cargo.toml",
+                r#"
+                [package]
+                name = "governance"
+
+                [package.metadata.nebula.imports]
+                voting = { path = "voting.wasm", require = ["vote"] }
+            "#,
+            )?;
+            jail.create_file("governance/Cargo.lock", "version = 3")?;
+            let wasm = test_support::module(&[
+                (wasm::SPEC_SECTION, &test_support::spec()),
+                (wasm::ENV_META_SECTION, &[]),
+            ]);
+            std::fs::write(jail.directory().join("governance/voting.wasm"), &wasm).unwrap();
+
+            let resolved = Importer::new()
+                .manifest("governance/Cargo.toml")
+                .cache("cache")
+                .out_dir("out")
+                .blocking_sync()
+                .unwrap();
+            let config = load_config(Path::new("governance/Cargo.toml")).unwrap();
+            assert_eq!(
+                config.vendor_dir(),
+                Path::new("governance/vendor/nebula/governance")
+            );
+            let voting = &resolved.modules["voting"];
+            assert_eq!(voting.wasm_sha256, hex::encode(Sha256::digest(&wasm)));
+            assert_eq!(voting.digest, format!("sha256:{}", voting.wasm_sha256));
+            assert_eq!(voting.reference, "path+voting.wasm");
+            assert!(voting.path.starts_with("cache"));
+            assert!(Path::new("out/nebula_importer/voting.wasm").is_file());
+            let code = std::fs::read_to_string("out/nebula_importer.rs").unwrap();
+            assert!(code.contains("mod voting"));
+            // Local imports are not locked.
+            assert!(resolved.lockfile.contracts.is_empty());
+
+            // The manifest given is read, not the `Cargo.toml` next to it.
+            let manifest = std::fs::read_to_string("governance/Cargo.toml").unwrap();
+            jail.create_file(
+                "governance/Renamed.toml",
+                &manifest.replace("voting = ", "ballot = "),
+            )?;
+            let resolved = Importer::new()
+                .manifest("governance/Renamed.toml")
+                .cache("cache")
+                .blocking_sync()
+                .unwrap();
+            assert!(resolved.modules.contains_key("ballot"));
+
+            // Requirements are checked without generating bindings too.
+            jail.create_file(
+                "governance/Cargo.toml",
+                &manifest.replace(r#"["vote"]"#, r#"["init"]"#),
+            )?;
+            // Async callers bring their own runtime.
+            let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+            let importer = Importer::new()
+                .manifest("governance/Cargo.toml")
+                .cache("cache");
+            let error = runtime.block_on(importer.sync()).unwrap_err();
+            assert!(matches!(
+                error,
+                Error::Import { source, .. } if matches!(*source, Error::InterfaceMismatch { .. })
+            ));
+            Ok(())
+        });
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn test_vendor() {
+        figment::Jail::expect_with(|jail| {
+            let manifest = r#"
+                [package]
+                name = "governance"
+
+                [package.metadata.nebula]
+                cache = "cache"
+
+                [package.metadata.nebula.imports]
+                voting = { path = "voting.wasm" }
+            "#;
+            jail.create_file("Cargo.toml", manifest)?;
+            std::fs::write("voting.wasm", test_support::contract()).unwrap();
+            // Modules of another package and files put there by hand.
+            jail.create_dir("vendor/nebula/marketplace")?;
+            jail.create_file("vendor/nebula/marketplace/voting.wasm", "")?;
+            jail.create_dir("vendor/nebula/governance")?;
+            jail.create_file("vendor/nebula/governance/patched.wasm", "")?;
+
+            let dir = vendor_all_contracts(Path::new("Cargo.toml"), None).unwrap();
+            assert_eq!(dir, Path::new("./vendor/nebula/governance"));
+            assert!(dir.join("voting.wasm").is_file());
+            let record = std::fs::read_to_string(dir.join(VENDORED)).unwrap();
+            assert!(record.lines().any(|line| line == "voting.wasm"));
+
+            jail.create_file("Cargo.toml", &manifest.replace("voting = ", "ballot = "))?;
+            vendor_all_contracts(Path::new("Cargo.toml"), None).unwrap();
+            assert!(!dir.join("voting.wasm").exists());
+            assert!(dir.join("ballot.wasm").is_file());
+            assert!(dir.join("patched.wasm").is_file());
+            assert!(Path::new("vendor/nebula/marketplace/voting.wasm").is_file());
+
+            // A given directory gets a subdirectory for the package too.
+            let dir =
+                vendor_all_contracts(Path::new("Cargo.toml"), Some(Path::new("shared"))).unwrap();
+            assert_eq!(dir, Path::new("shared/governance"));
+            assert!(dir.join("ballot.wasm").is_file());
+
+            // Paths are relative to the manifest, not the current directory.
+            jail.change_dir(jail.create_dir("elsewhere")?)?;
+            let dir = vendor_all_contracts(Path::new("../Cargo.toml"), None).unwrap();
+            assert_eq!(dir, Path::new("../vendor/nebula/governance"));
+            assert!(!Path::new("cache").exists());
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_check_interface() {
+        let sdk = interface::Sdk {
+            version: "20.5.0".parse().unwrap(),
+        };
+        let check = |version: u64| check_interface("voting", version.into(), &sdk, Severity::Deny);
+        assert!(check(20 << 32).is_ok());
+        assert!(matches!(
+            check(21 << 32),
+            Err(Error::IncompatibleInterface { .. })
+        ));
+        let error = check((20 << 32) | 1).unwrap_err();
+        assert!(matches!(error, Error::PreReleaseInterface { .. }));
+        assert!(error.to_string().contains("pre-release 1"));
+        assert!(check_interface("voting", (21 << 32).into(), &sdk, Severity::Warn).is_ok());
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn test_jobs() {
+        figment::Jail::expect_with(|jail| {
+            let mut config = Config::default();
+            assert_eq!(config.jobs().unwrap(), DEFAULT_JOBS);
+            config.jobs = Some(2);
+            assert_eq!(config.jobs().unwrap(), 2);
+            jail.set_env("NEBULA_JOBS", "8");
+            assert_eq!(config.jobs().unwrap(), 8);
+            jail.set_env("NEBULA_JOBS", "0");
+            assert!(matches!(config.jobs(), Err(Error::InvalidJobs(_))));
             Ok(())
         });
     }
+
+    #[test]
+    fn test_import_kinds() {
+        let config: Config = toml::from_str(
+            r#"
+            [imports]
+            token = "ghcr.io/eigerco/nebula/contracts/token"
+            pinned = { reference = "ghcr.io/eigerco/nebula/contracts/voting", digest = "sha256:00" }
+            local = { path = "../voting/target/wasm32-unknown-unknown/release/voting.wasm" }
+            remote = { url = "https://example.com/voting.wasm", sha256 = "00" }
+            ranged = { reference = "ghcr.io/eigerco/nebula/contracts/voting", version = "^0.2" }
+            titled = { reference = "ghcr.io/eigerco/nebula/contracts/bundle", layer = "voting.wasm" }
+            bundle = { reference = "ghcr.io/eigerco/nebula/contracts/bundle", all_layers = true }
+            checked = { reference = "ghcr.io/eigerco/nebula/contracts/voting:v1", require = ["init", "fn vote(proposal: u32) -> bool"] }
+            specified = { path = "voting.wasm", require = "voting.interface" }
+            "#,
+        )
+        .unwrap();
+        assert!(matches!(config.imports["token"], Contract::NoDigest(_)));
+        assert!(matches!(
+            config.imports["pinned"],
+            Contract::WithDigest {
+                digest: Some(_),
+                ..
+            }
+        ));
+        assert!(matches!(config.imports["local"], Contract::Path { .. }));
+        assert!(config.imports["token"].require().is_none());
+        assert!(matches!(
+            config.imports["checked"].require(),
+            Some(Require::Functions(functions)) if functions.len() == 2
+        ));
+        assert!(matches!(
+            config.imports["specified"].require(),
+            Some(Require::File(path)) if path == Path::new("voting.interface")
+        ));
+        assert!(matches!(
+            &config.imports["ranged"],
+            Contract::Versioned { version, .. } if version.to_string() == "^0.2"
+        ));
+        assert!(matches!(
+            &config.imports["titled"],
+            Contract::WithDigest {
+                layer: Some(Layer::Title(title)),
+                all_layers: false,
+                ..
+            } if title == "voting.wasm"
+        ));
+        assert!(matches!(
+            config.imports["bundle"],
+            Contract::WithDigest {
+                layer: None,
+                all_layers: true,
+                ..
+            }
+        ));
+        assert!(matches!(
+            config.imports["remote"],
+            Contract::Url {
+                sha256: Some(_),
+                ..
+            }
+        ));
+        assert!(config.validate().is_ok());
+
+        let config: Config = toml::from_str(
+            r#"
+            [imports]
+            bundle = { reference = "ghcr.io/eigerco/nebula/contracts/bundle", all_layers = true, require = ["vote"] }
+        "#,
+        )
+        .unwrap();
+        assert!(matches!(
+            config.validate(),
+            Err(Error::RequireWithAllLayers(name)) if name == "bundle"
+        ));
+    }
+
+    #[test]
+    fn test_verify_layer() {
+        let digest = format!("sha256:{}", hex::encode(Sha256::digest(b"\0asm")));
+        assert!(verify_layer("voting", b"\0asm", &digest).is_ok());
+        assert!(matches!(
+            verify_layer("voting", b"\0asn", &digest),
+            Err(Error::DigestMismatch { .. })
+        ));
+        assert!(verify_layer("voting", b"\0asm", &format!("sha512:{}", "0".repeat(128))).is_err());
+    }
+
+    #[test]
+    fn test_wasm_layers() {
+        let descriptor = |media_type: &str| manifest::OciDescriptor {
+            media_type: media_type.to_string(),
+            digest: format!("sha256:{}", "0".repeat(64)),
+            size: 0,
+            urls: None,
+            annotations: None,
+        };
+        let mut image_manifest = manifest::OciImageManifest {
+            schema_version: 2,
+            media_type: None,
+            config: descriptor(manifest::WASM_CONFIG_MEDIA_TYPE),
+            layers: vec![descriptor(manifest::WASM_LAYER_MEDIA_TYPE)],
+            annotations: None,
+        };
+        assert!(wasm_layers("voting", &image_manifest, Selection::First).is_ok());
+
+        image_manifest.layers = vec![descriptor("application/vnd.oci.image.layer.v1.tar+gzip")];
+        assert!(matches!(
+            wasm_layers("voting", &image_manifest, Selection::First),
+            Err(Error::WrongMediaType { found, .. }) if found.ends_with("tar+gzip")
+        ));
+        image_manifest.layers.clear();
+        assert!(matches!(
+            wasm_layers("voting", &image_manifest, Selection::First),
+            Err(Error::MissingLayer { .. })
+        ));
+
+        image_manifest.config = descriptor("application/vnd.oci.image.config.v1+json");
+        image_manifest.layers = vec![descriptor(manifest::WASM_LAYER_MEDIA_TYPE)];
+        assert!(matches!(
+            wasm_layers("voting", &image_manifest, Selection::First),
+            Err(Error::WrongMediaType { found, .. }) if found.contains("image.config")
+        ));
+    }
+
+    #[test]
+    fn test_select_layers() {
+        let layer = |media_type: &str, title: Option<&str>| manifest::OciDescriptor {
+            media_type: media_type.to_string(),
+            digest: format!("sha256:{}", "0".repeat(64)),
+            size: 0,
+            urls: None,
+            annotations: title.map(|title| {
+                HashMap::from([(
+                    annotations::ORG_OPENCONTAINERS_IMAGE_TITLE.to_string(),
+                    title.to_string(),
+                )])
+            }),
+        };
+        let image_manifest = manifest::OciImageManifest {
+            schema_version: 2,
+            media_type: None,
+            config: layer(manifest::WASM_CONFIG_MEDIA_TYPE, None),
+            layers: vec![
+                layer("text/markdown", Some("README.md")),
+                layer(manifest::WASM_LAYER_MEDIA_TYPE, Some("governance.wasm")),
+                layer(manifest::WASM_LAYER_MEDIA_TYPE, Some("voting.wasm")),
+            ],
+            annotations: None,
+        };
+        let positions = |selection| {
+            wasm_layers("bundle", &image_manifest, selection)
+                .unwrap()
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(positions(Selection::First), [1]);
+        assert_eq!(positions(Selection::All), [1, 2]);
+        assert_eq!(positions(Selection::One(&Layer::Index(2))), [2]);
+        let voting = Layer::Title("voting.wasm".to_string());
+        assert_eq!(positions(Selection::One(&voting)), [2]);
+
+        assert!(matches!(
+            wasm_layers("bundle", &image_manifest, Selection::One(&Layer::Index(0))),
+            Err(Error::WrongMediaType { found, .. }) if found == "text/markdown"
+        ));
+        let missing = Layer::Title("token.wasm".to_string());
+        assert!(matches!(
+            wasm_layers("bundle", &image_manifest, Selection::One(&missing)),
+            Err(Error::LayerNotFound { layer, .. }) if layer == "token.wasm"
+        ));
+
+        assert_eq!(
+            layer_module("bundle", 2, Some("voting.wasm")),
+            "bundle_voting"
+        );
+        assert_eq!(
+            layer_module("bundle", 1, Some("Gov-Token.wasm")),
+            "bundle_gov_token"
+        );
+        assert_eq!(layer_module("bundle", 3, None), "bundle_3");
+    }
+
+    #[test]
+    fn test_generate_modules() {
+        let mut modules = BTreeMap::new();
+        let spec = wasm::contract_spec(&test_support::module(&[(
+            wasm::SPEC_SECTION,
+            &test_support::spec(),
+        )]))
+        .unwrap();
+        let bindings = Bindings::default();
+        let module = ResolvedModule {
+            path: PathBuf::new(),
+            reference: "path+voting.wasm".into(),
+            digest: format!("sha256:{}", "0".repeat(64)),
+            wasm_sha256: "0".repeat(64),
+            annotations: BTreeMap::new(),
+        };
+        for name in ["voting", "token", "governance"] {
+            let wasm = format!("/{MODULES_DIR}/{name}.wasm");
+            modules.insert(
+                name.to_string(),
+                codegen::contract_module(name, &spec, &wasm, &module, &bindings).unwrap(),
+            );
+        }
+        let code = generate_modules(&modules);
+
+        let positions: Vec<_> = ["governance", "token", "voting"]
+            .iter()
+            .map(|name| code.find(&format!("mod {name}")).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert!(code.contains("/nebula_importer/token.wasm"));
+        assert!(matches!(
+            codegen::contract_module("my-contract", &spec, "/a.wasm", &module, &bindings),
+            Err(Error::InvalidName(_))
+        ));
+    }
 }
@@ -0,0 +1,181 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{Error, Layer};
+
+/// Name of the lockfile, stored next to `Cargo.toml`.
+pub const LOCKFILE: &str = "Nebula.lock";
+
+const HEADER: &str = "# This file is automatically @generated by nebula-importer.\n\
+                      # It is not intended for manual editing.\n";
+
+/// Pins every import to the manifest digest it resolved to, so later builds
+/// pull exactly the same Wasm module.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct Lockfile {
+    #[serde(default, rename = "contract")]
+    pub contracts: Vec<LockedContract>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LockedContract {
+    /// The import name, as written in `[package.metadata.nebula.imports]`.
+    pub name: String,
+    /// The reference as configured, e.g. `ghcr.io/eigerco/nebula/contracts/voting:latest`.
+    pub reference: String,
+    /// The tag picked for a `version` requirement, e.g. `v0.2.3`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// The imports whose artifacts declare this one, for imports that are
+    /// not configured but required by other imports.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_by: Vec<String>,
+    /// The manifest digest the reference resolved to.
+    pub digest: String,
+    /// Hex encoded sha256 of the Wasm module, of the first one for imports
+    /// of all layers.
+    pub wasm_sha256: String,
+    /// The layer the import selected, if not the first Wasm layer.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layer: Option<Layer>,
+    /// The annotations of the artifact, kept so builds from the lockfile
+    /// generate the same constants without fetching the manifest.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+    /// Every module of an import of all layers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub layers: Vec<LockedLayer>,
+}
+
+/// A module of an import of all the layers of an artifact.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LockedLayer {
+    /// The name of the generated module.
+    pub name: String,
+    /// Hex encoded sha256 of the Wasm module.
+    pub wasm_sha256: String,
+}
+
+impl LockedContract {
+    /// The modules the import generates, a single one unless it imports
+    /// all layers.
+    pub fn modules(&self) -> Vec<LockedLayer> {
+        if !self.layers.is_empty() {
+            return self.layers.clone();
+        }
+        vec![LockedLayer {
+            name: self.name.clone(),
+            wasm_sha256: self.wasm_sha256.clone(),
+        }]
+    }
+}
+
+impl Lockfile {
+    /// Reads a lockfile, returning an empty one if the file does not exist.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        if !path.try_exists().map_err(Error::io(path))? {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path).map_err(Error::io(path))?;
+        toml::from_str(&text).map_err(|source| Error::Parse {
+            path: path.into(),
+            source,
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let text = toml::to_string(self).map_err(|source| Error::Serialize {
+            path: path.into(),
+            source,
+        })?;
+        std::fs::write(path, format!("{HEADER}\n{text}")).map_err(Error::io(path))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&LockedContract> {
+        self.contracts.iter().find(|contract| contract.name == name)
+    }
+
+    /// Adds or replaces the entry for a contract, keeping entries sorted by name.
+    pub fn insert(&mut self, contract: LockedContract) {
+        match self
+            .contracts
+            .binary_search_by(|locked| locked.name.cmp(&contract.name))
+        {
+            Ok(index) => self.contracts[index] = contract,
+            Err(index) => self.contracts.insert(index, contract),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked(name: &str) -> LockedContract {
+        LockedContract {
+            name: name.to_string(),
+            reference: format!("ghcr.io/eigerco/nebula/contracts/{name}:latest"),
+            version: None,
+            required_by: Vec::new(),
+            digest: format!("sha256:{}", "0".repeat(64)),
+            wasm_sha256: "1".repeat(64),
+            layer: None,
+            annotations: BTreeMap::new(),
+            layers: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_insert_keeps_order() {
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(locked("voting"));
+        lockfile.insert(locked("governance"));
+        lockfile.insert(locked("token"));
+        lockfile.insert(locked("voting"));
+
+        let names: Vec<_> = lockfile.contracts.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["governance", "token", "voting"]);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(locked("voting"));
+        lockfile.insert(locked("token"));
+
+        let text = format!("{HEADER}\n{}", toml::to_string(&lockfile).unwrap());
+        let parsed: Lockfile = toml::from_str(&text).unwrap();
+        assert_eq!(parsed, lockfile);
+        assert_eq!(parsed.get("token"), Some(&locked("token")));
+    }
+
+    #[test]
+    fn test_layers() {
+        let mut bundle = locked("bundle");
+        assert!(!toml::to_string(&bundle).unwrap().contains("layer"));
+        assert_eq!(bundle.modules()[0].name, "bundle");
+
+        bundle.layers = vec![
+            LockedLayer {
+                name: "bundle_governance".to_string(),
+                wasm_sha256: "1".repeat(64),
+            },
+            LockedLayer {
+                name: "bundle_voting".to_string(),
+                wasm_sha256: "2".repeat(64),
+            },
+        ];
+        let mut voting = locked("voting");
+        voting.layer = Some(Layer::Title("voting.wasm".to_string()));
+        voting.annotations = BTreeMap::from([(
+            "org.opencontainers.image.version".to_string(),
+            "0.2.0".to_string(),
+        )]);
+        let mut lockfile = Lockfile::default();
+        lockfile.insert(bundle.clone());
+        lockfile.insert(voting.clone());
+        let parsed: Lockfile = toml::from_str(&toml::to_string(&lockfile).unwrap()).unwrap();
+        assert_eq!(parsed.get("bundle").unwrap().modules(), bundle.layers);
+        assert_eq!(parsed.get("voting"), Some(&voting));
+    }
+}
@@ -0,0 +1,171 @@
+use clap::{Parser, Subcommand};
+use nebula_importer::{Cache, CachedModule, Error, LockedContract, Lockfile, Prune};
+use std::{
+    collections::HashSet,
+    path::PathBuf,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Cli {
+    /// Path to the `Cargo.toml` of the package importing contracts
+    #[clap(long, default_value = "Cargo.toml")]
+    manifest_path: PathBuf,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Copy every import into a directory that builds prefer over the registry
+    Vendor {
+        /// Defaults to `vendor` in the nebula metadata, or `vendor/nebula`
+        /// next to the manifest. Modules are copied to a subdirectory named
+        /// after the package
+        dir: Option<PathBuf>,
+    },
+    /// Print the function signatures of a module, one per line as `require`
+    /// files expect them
+    Interface { module: PathBuf },
+    /// Inspect and clean up the module cache
+    Cache {
+        /// Defaults to `cache` in the nebula metadata, or the user's data directory
+        #[clap(long)]
+        dir: Option<PathBuf>,
+        #[command(subcommand)]
+        command: CacheCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheCommand {
+    /// List cached modules with the references resolving to them
+    List,
+    /// Hash every cached module again and report corrupted ones
+    Verify,
+    /// Remove modules not locked by the given lockfiles or not used recently
+    Prune {
+        /// Keep the modules locked by this `Nebula.lock`, may be repeated
+        #[clap(long = "lockfile", required_unless_present = "unused_for")]
+        lockfiles: Vec<PathBuf>,
+        /// Remove modules not used for this many days
+        #[clap(long)]
+        unused_for: Option<u64>,
+    },
+    /// Remove every cached module
+    Clear,
+}
+
+fn main() {
+    let Cli {
+        manifest_path,
+        command,
+    } = Cli::parse();
+    let result = match command {
+        Command::Vendor { dir } => {
+            nebula_importer::vendor_all_contracts(&manifest_path, dir.as_deref())
+                .map(|dir| println!("Vendored contracts into {}", dir.display()))
+        }
+        Command::Interface { module } => {
+            nebula_importer::module_signatures(&module).map(|signatures| {
+                for signature in signatures {
+                    println!("{signature}");
+                }
+            })
+        }
+        Command::Cache { dir, command } => dir
+            .map(|dir| Ok(Cache::new(dir)))
+            .unwrap_or_else(|| nebula_importer::configured_cache(&manifest_path))
+            .and_then(|cache| run_cache(&cache, command)),
+    };
+    if let Err(error) = result {
+        for error in error.errors() {
+            eprintln!("error: {error}");
+            let mut source = std::error::Error::source(error);
+            while let Some(cause) = source {
+                eprintln!("  caused by: {cause}");
+                source = cause.source();
+            }
+            eprintln!("hint: {}", error.hint());
+        }
+        std::process::exit(1);
+    }
+}
+
+fn run_cache(cache: &Cache, command: CacheCommand) -> Result<(), Error> {
+    match command {
+        CacheCommand::List => {
+            for module in cache.list()? {
+                print_module(&module);
+            }
+        }
+        CacheCommand::Verify => {
+            let corrupted = cache.verify()?;
+            for module in &corrupted {
+                println!("corrupted: {}", module.path.display());
+            }
+            if !corrupted.is_empty() {
+                eprintln!(
+                    "error: {} corrupted modules, remove them with `nebula-importer cache prune` or `clear`",
+                    corrupted.len()
+                );
+                std::process::exit(1);
+            }
+            println!("All cached modules match their sha256");
+        }
+        CacheCommand::Prune {
+            lockfiles,
+            unused_for,
+        } => {
+            let keep = if lockfiles.is_empty() {
+                None
+            } else {
+                let mut keep = HashSet::new();
+                for path in &lockfiles {
+                    let lockfile = Lockfile::load(path)?;
+                    keep.extend(
+                        lockfile
+                            .contracts
+                            .iter()
+                            .flat_map(LockedContract::modules)
+                            .map(|module| module.wasm_sha256),
+                    );
+                }
+                Some(keep)
+            };
+            let prune = Prune {
+                keep,
+                unused_for: unused_for.map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+            };
+            let removed = cache.prune(&prune)?;
+            let size: u64 = removed.iter().map(|module| module.size).sum();
+            println!("Removed {} modules, {size} bytes", removed.len());
+        }
+        CacheCommand::Clear => {
+            cache.clear()?;
+            println!("Cleared {}", cache.root().display());
+        }
+    }
+    Ok(())
+}
+
+fn print_module(module: &CachedModule) {
+    let last_used = match module.last_used() {
+        Some(last_used) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|now| now.as_secs())
+                .unwrap_or_default();
+            format!(
+                "used {} days ago",
+                now.saturating_sub(last_used) / (24 * 60 * 60)
+            )
+        }
+        None => "never used".to_string(),
+    };
+    println!("{} {} bytes, {last_used}", module.wasm_sha256, module.size);
+    for (reference, entry) in &module.references {
+        println!("    {reference} ({})", entry.digest);
+    }
+}
@@ -0,0 +1,253 @@
+//! Per-registry settings, configured under
+//! `[package.metadata.nebula.registries."<host or prefix>"]`.
+
+use oci_distribution::{
+    client::{Certificate, CertificateEncoding, ClientConfig, ClientProtocol},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use crate::Error;
+
+/// Environment variable overriding the configured mirrors, e.g.
+/// `ghcr.io=mirror.example.com/ghcr,ghcr.io;docker.io=mirror.example.com/hub`.
+pub const MIRRORS_ENV: &str = "NEBULA_MIRRORS";
+
+/// Settings of a registry host or of every repository under a prefix.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RegistryConfig {
+    /// Hosts or prefixes to pull from instead, tried in order. List the
+    /// original as well to fall back to it.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// Talk to the registry over plain HTTP, e.g. a local `registry:2`.
+    #[serde(default)]
+    pub insecure: bool,
+    /// PEM or DER encoded root certificates to trust in addition to the
+    /// system ones, relative to `Cargo.toml`.
+    #[serde(default)]
+    pub ca_certs: Vec<PathBuf>,
+    /// Accept any certificate, including self-signed and expired ones.
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+}
+
+impl RegistryConfig {
+    fn has_client_settings(&self) -> bool {
+        self.insecure || self.accept_invalid_certs || !self.ca_certs.is_empty()
+    }
+}
+
+/// A client per registry host with connection settings, and a default one
+/// for every other registry.
+#[derive(Clone)]
+pub(crate) struct Clients {
+    default: Client,
+    registries: HashMap<String, Client>,
+}
+
+impl Clients {
+    pub fn new(registries: &BTreeMap<String, RegistryConfig>) -> Result<Self, Error> {
+        // Connection settings apply to a whole host, even when configured
+        // for a prefix.
+        let mut hosts: BTreeMap<&str, Vec<&RegistryConfig>> = BTreeMap::new();
+        for (prefix, registry) in registries {
+            if registry.has_client_settings() {
+                hosts.entry(host(prefix)).or_default().push(registry);
+            }
+        }
+        let registries = hosts
+            .into_iter()
+            .map(|(host, settings)| Ok((host.to_string(), Client::new(client_config(&settings)?))))
+            .collect::<Result<_, Error>>()?;
+        Ok(Clients {
+            default: Client::new(ClientConfig {
+                protocol: ClientProtocol::Https,
+                ..Default::default()
+            }),
+            registries,
+        })
+    }
+
+    /// The client for a registry host, clones share connections.
+    pub fn for_registry(&self, registry: &str) -> Client {
+        self.registries
+            .get(registry)
+            .unwrap_or(&self.default)
+            .clone()
+    }
+}
+
+fn host(prefix: &str) -> &str {
+    prefix.split('/').next().unwrap_or(prefix)
+}
+
+fn client_config(settings: &[&RegistryConfig]) -> Result<ClientConfig, Error> {
+    let insecure = settings.iter().any(|registry| registry.insecure);
+    let mut extra_root_certificates = Vec::new();
+    for path in settings.iter().flat_map(|registry| &registry.ca_certs) {
+        extra_root_certificates.push(load_certificate(path)?);
+    }
+    Ok(ClientConfig {
+        protocol: if insecure {
+            ClientProtocol::Http
+        } else {
+            ClientProtocol::Https
+        },
+        accept_invalid_certificates: settings
+            .iter()
+            .any(|registry| registry.accept_invalid_certs),
+        extra_root_certificates,
+        ..Default::default()
+    })
+}
+
+fn load_certificate(path: &Path) -> Result<Certificate, Error> {
+    crate::rerun::file(path);
+    let data = std::fs::read(path).map_err(Error::io(path))?;
+    let encoding = if data.starts_with(b"-----BEGIN") {
+        CertificateEncoding::Pem
+    } else {
+        CertificateEncoding::Der
+    };
+    Ok(Certificate { encoding, data })
+}
+
+/// Rewrites references to their mirrors.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Mirrors {
+    /// Mirrors by host or prefix.
+    rules: BTreeMap<String, Vec<String>>,
+}
+
+impl Mirrors {
+    /// Combines the configured mirrors with the ones from [`MIRRORS_ENV`],
+    /// which take precedence for the same prefix.
+    pub fn new(registries: &BTreeMap<String, RegistryConfig>, env: Option<&str>) -> Self {
+        let mut rules: BTreeMap<_, _> = registries
+            .iter()
+            .filter(|(_, registry)| !registry.mirrors.is_empty())
+            .map(|(prefix, registry)| (prefix.clone(), registry.mirrors.clone()))
+            .collect();
+        for rule in env.unwrap_or_default().split(';') {
+            let Some((prefix, mirrors)) = rule.split_once('=') else {
+                continue;
+            };
+            let mirrors = mirrors
+                .split(',')
+                .map(str::trim)
+                .filter(|mirror| !mirror.is_empty())
+                .map(str::to_string)
+                .collect();
+            rules.insert(prefix.trim().to_string(), mirrors);
+        }
+        Mirrors { rules }
+    }
+
+    /// The references to try for `reference`, in order.
+    ///
+    /// The longest matching prefix wins; it has to end at a `/`, `:` or `@`
+    /// so `ghcr.io` does not match `ghcr.io.example.com`. References without
+    /// mirrors are returned as is.
+    pub fn candidates(&self, reference: &str) -> Vec<String> {
+        let rule = self
+            .rules
+            .iter()
+            .filter(|(prefix, _)| {
+                reference
+                    .strip_prefix(prefix.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', ':', '@']))
+            })
+            .max_by_key(|(prefix, _)| prefix.len());
+        match rule {
+            Some((prefix, mirrors)) if !mirrors.is_empty() => mirrors
+                .iter()
+                .map(|mirror| format!("{mirror}{}", &reference[prefix.len()..]))
+                .collect(),
+            _ => vec![reference.to_string()],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mirrors() {
+        let registries = BTreeMap::from([
+            (
+                "ghcr.io".to_string(),
+                RegistryConfig {
+                    mirrors: vec!["mirror.example.com/ghcr".into(), "ghcr.io".into()],
+                    ..Default::default()
+                },
+            ),
+            (
+                "ghcr.io/eigerco/nebula".to_string(),
+                RegistryConfig {
+                    mirrors: vec!["nebula.example.com".into()],
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let mirrors = Mirrors::new(&registries, None);
+        assert_eq!(
+            mirrors.candidates("ghcr.io/other/voting:v1"),
+            vec![
+                "mirror.example.com/ghcr/other/voting:v1",
+                "ghcr.io/other/voting:v1"
+            ]
+        );
+        assert_eq!(
+            mirrors.candidates("ghcr.io/eigerco/nebula/contracts/voting@sha256:00"),
+            vec!["nebula.example.com/contracts/voting@sha256:00"]
+        );
+        assert_eq!(
+            mirrors.candidates("ghcr.io.example.com/voting:v1"),
+            vec!["ghcr.io.example.com/voting:v1"]
+        );
+
+        let mirrors = Mirrors::new(&registries, Some("ghcr.io=proxy.local/ghcr; docker.io="));
+        assert_eq!(
+            mirrors.candidates("ghcr.io/other/voting:v1"),
+            vec!["proxy.local/ghcr/other/voting:v1"]
+        );
+        assert_eq!(
+            mirrors.candidates("docker.io/library/voting:v1"),
+            vec!["docker.io/library/voting:v1"]
+        );
+    }
+
+    #[test]
+    fn test_client_settings() {
+        let registries: BTreeMap<String, RegistryConfig> = toml::from_str(
+            r#"
+            "localhost:5000" = { insecure = true }
+            "registry.example.com/team" = { accept_invalid_certs = true }
+            "ghcr.io" = { mirrors = ["mirror.example.com"] }
+            "#,
+        )
+        .unwrap();
+        let clients = Clients::new(&registries).unwrap();
+        let mut hosts: Vec<_> = clients.registries.keys().map(String::as_str).collect();
+        hosts.sort();
+        assert_eq!(hosts, ["localhost:5000", "registry.example.com"]);
+
+        let config = client_config(&[&registries["localhost:5000"]]).unwrap();
+        assert!(matches!(config.protocol, ClientProtocol::Http));
+        let config = client_config(&[&registries["registry.example.com/team"]]).unwrap();
+        assert!(matches!(config.protocol, ClientProtocol::Https));
+        assert!(config.accept_invalid_certificates);
+
+        let missing = RegistryConfig {
+            ca_certs: vec!["/nonexistent/ca.pem".into()],
+            ..Default::default()
+        };
+        assert!(matches!(client_config(&[&missing]), Err(Error::Io { .. })));
+    }
+}
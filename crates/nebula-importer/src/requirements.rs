@@ -0,0 +1,196 @@
+//! Interface requirements of imports: the functions, and optionally their
+//! exact signatures, the importing contract relies on.
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+use stellar_xdr::curr::{ScSpecEntry, ScSpecFunctionV0, ScSpecTypeDef};
+
+use crate::Error;
+
+/// What an imported module has to provide, configured with `require` on the
+/// import.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Require {
+    /// Function names, e.g. `"vote"`, or full signatures, e.g.
+    /// `"fn vote(proposal: u32) -> bool"`.
+    Functions(Vec<String>),
+    /// A file with a function name or signature per line, relative to
+    /// `Cargo.toml`. Empty lines and lines starting with `#` are skipped.
+    File(PathBuf),
+}
+
+impl Require {
+    pub(crate) fn rebase(&mut self, root: &Path) {
+        if let Require::File(path) = self {
+            *path = root.join(&*path);
+        }
+    }
+
+    fn entries(&self) -> Result<Vec<String>, Error> {
+        match self {
+            Require::Functions(functions) => Ok(functions.clone()),
+            Require::File(path) => {
+                crate::rerun::file(path);
+                let text = std::fs::read_to_string(path).map_err(Error::io(path))?;
+                Ok(text
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .map(str::to_string)
+                    .collect())
+            }
+        }
+    }
+
+    /// Checks the interface of a module against the requirements, listing
+    /// every missing function and every differing signature.
+    pub(crate) fn check(&self, spec: &[ScSpecEntry]) -> Result<(), Error> {
+        let found: HashMap<_, _> = signatures(spec)
+            .into_iter()
+            .map(|signature| (function_name(&signature).to_string(), signature))
+            .collect();
+        let mut diff = Vec::new();
+        for entry in self.entries()? {
+            let entry = entry.trim();
+            let name = function_name(entry);
+            let required = entry.starts_with("fn ");
+            match found.get(name) {
+                None if required => diff.push(format!("- {entry}")),
+                None => diff.push(format!("- fn {name}")),
+                Some(signature) if required && normalize(signature) != normalize(entry) => {
+                    diff.push(format!("- {entry}"));
+                    diff.push(format!("+ {signature}"));
+                }
+                Some(_) => {}
+            }
+        }
+        if diff.is_empty() {
+            return Ok(());
+        }
+        Err(Error::InterfaceMismatch { diff })
+    }
+}
+
+/// Renders the functions of a contract as Rust-like signatures, e.g.
+/// `fn vote(proposal: u32) -> bool`, in the order of its spec.
+pub(crate) fn signatures(spec: &[ScSpecEntry]) -> Vec<String> {
+    spec.iter()
+        .filter_map(|entry| match entry {
+            ScSpecEntry::FunctionV0(function) => Some(signature(function)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn signature(function: &ScSpecFunctionV0) -> String {
+    let inputs: Vec<_> = function
+        .inputs
+        .iter()
+        .map(|input| {
+            format!(
+                "{}: {}",
+                input.name.to_utf8_string_lossy(),
+                type_name(&input.type_)
+            )
+        })
+        .collect();
+    let name = function.name.to_utf8_string_lossy();
+    match function.outputs.first() {
+        Some(output) => format!("fn {name}({}) -> {}", inputs.join(", "), type_name(output)),
+        None => format!("fn {name}({})", inputs.join(", ")),
+    }
+}
+
+/// The name of a type as the generated bindings spell it.
+fn type_name(type_: &ScSpecTypeDef) -> String {
+    match type_ {
+        ScSpecTypeDef::Val => "Val".into(),
+        ScSpecTypeDef::Bool => "bool".into(),
+        ScSpecTypeDef::Void => "()".into(),
+        ScSpecTypeDef::Error => "Error".into(),
+        ScSpecTypeDef::U32 => "u32".into(),
+        ScSpecTypeDef::I32 => "i32".into(),
+        ScSpecTypeDef::U64 => "u64".into(),
+        ScSpecTypeDef::I64 => "i64".into(),
+        ScSpecTypeDef::Timepoint => "Timepoint".into(),
+        ScSpecTypeDef::Duration => "Duration".into(),
+        ScSpecTypeDef::U128 => "u128".into(),
+        ScSpecTypeDef::I128 => "i128".into(),
+        ScSpecTypeDef::U256 => "U256".into(),
+        ScSpecTypeDef::I256 => "I256".into(),
+        ScSpecTypeDef::Bytes => "Bytes".into(),
+        ScSpecTypeDef::String => "String".into(),
+        ScSpecTypeDef::Symbol => "Symbol".into(),
+        ScSpecTypeDef::Address => "Address".into(),
+        ScSpecTypeDef::Option(option) => format!("Option<{}>", type_name(&option.value_type)),
+        ScSpecTypeDef::Result(result) => format!(
+            "Result<{}, {}>",
+            type_name(&result.ok_type),
+            type_name(&result.error_type)
+        ),
+        ScSpecTypeDef::Vec(vec) => format!("Vec<{}>", type_name(&vec.element_type)),
+        ScSpecTypeDef::Map(map) => format!(
+            "Map<{}, {}>",
+            type_name(&map.key_type),
+            type_name(&map.value_type)
+        ),
+        ScSpecTypeDef::Tuple(tuple) => {
+            let types: Vec<_> = tuple.value_types.iter().map(type_name).collect();
+            format!("({})", types.join(", "))
+        }
+        ScSpecTypeDef::BytesN(bytes) => format!("BytesN<{}>", bytes.n),
+        ScSpecTypeDef::Udt(udt) => udt.name.to_utf8_string_lossy(),
+    }
+}
+
+/// The function a requirement refers to, `vote` in both `vote` and
+/// `fn vote(proposal: u32) -> bool`.
+fn function_name(entry: &str) -> &str {
+    match entry.strip_prefix("fn ") {
+        Some(signature) => signature.split('(').next().unwrap_or_default().trim(),
+        None => entry.trim(),
+    }
+}
+
+/// Signatures are compared regardless of whitespace.
+fn normalize(signature: &str) -> String {
+    signature
+        .strip_prefix("fn ")
+        .unwrap_or(signature)
+        .split_whitespace()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{test_support, wasm};
+
+    #[test]
+    fn test_check() {
+        let spec = wasm::contract_spec(&test_support::module(&[(
+            wasm::SPEC_SECTION,
+            &test_support::spec(),
+        )]))
+        .unwrap();
+        assert_eq!(signatures(&spec), ["fn vote(proposal: u32) -> bool"]);
+
+        let require = |functions: &[&str]| {
+            Require::Functions(functions.iter().map(|f| f.to_string()).collect()).check(&spec)
+        };
+        assert!(require(&["vote"]).is_ok());
+        assert!(require(&["fn vote(proposal:u32)->bool"]).is_ok());
+        assert!(matches!(
+            require(&["init", "fn vote(proposal: u64) -> bool"]),
+            Err(Error::InterfaceMismatch { diff }) if diff == [
+                "- fn init",
+                "- fn vote(proposal: u64) -> bool",
+                "+ fn vote(proposal: u32) -> bool",
+            ]
+        ));
+    }
+}
@@ -0,0 +1,58 @@
+//! `cargo:rerun-if-*` directives for everything the importer consults, so
+//! the build script reruns exactly when its inputs change.
+//!
+//! Once a build script emits any of these directives Cargo stops rerunning
+//! it on every package change, so every input has to be reported.
+//!
+//! Directives are only printed from build scripts. Elsewhere, e.g. from
+//! `nebula-importer vendor` or [`crate::Importer`], they would only clutter
+//! the output, and warnings go to stderr instead.
+
+use std::{collections::BTreeSet, path::Path, sync::Mutex};
+
+static EMITTED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+
+/// Whether the importer runs from a build script, which Cargo gives an
+/// `OUT_DIR`.
+fn in_build_script() -> bool {
+    std::env::var_os("OUT_DIR").is_some()
+}
+
+/// Prints a directive unless it was printed before.
+fn emit(directive: String) {
+    if !in_build_script() {
+        return;
+    }
+    let mut emitted = EMITTED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !emitted.contains(&directive) {
+        println!("{directive}");
+        emitted.insert(directive);
+    }
+}
+
+/// Reruns the build when `path` changes.
+///
+/// Paths that do not exist are skipped, Cargo would otherwise rerun the
+/// build script every time.
+pub(crate) fn file(path: &Path) {
+    if path.exists() {
+        emit(format!("cargo:rerun-if-changed={}", path.display()));
+    }
+}
+
+/// Reads an environment variable, rerunning the build when it changes.
+pub(crate) fn var(name: &str) -> Option<String> {
+    emit(format!("cargo:rerun-if-env-changed={name}"));
+    std::env::var(name).ok()
+}
+
+/// Reports a warning, shown by Cargo when running from a build script.
+pub(crate) fn warning(message: &str) {
+    if in_build_script() {
+        println!("cargo:warning={message}");
+    } else {
+        eprintln!("warning: {message}");
+    }
+}
@@ -0,0 +1,128 @@
+//! Imports that do not come from an OCI registry: local files and HTTP(S)
+//! downloads. Both go through the same cache as registry imports.
+
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+use crate::{Error, LockedContract, ResolvedModule, SyncContext};
+
+/// Copies a local module into the cache.
+///
+/// Local modules are not locked, they are expected to change while the
+/// contract they belong to is developed. The module is referred to by `path`
+/// as configured, relative to `root`, so the generated code does not depend
+/// on where the checkout is.
+pub(crate) async fn sync_path(
+    path: &Path,
+    root: &Path,
+    context: &SyncContext,
+) -> Result<ResolvedModule, Error> {
+    let file = root.join(path);
+    crate::rerun::file(&file);
+    let data = std::fs::read(&file).map_err(Error::io(&file))?;
+    crate::validate_contract(&file.display().to_string(), &data)?;
+    let wasm_sha256 = context.cache.write_blob(&data)?;
+    let digest = format!("sha256:{wasm_sha256}");
+    // The cache is shared between packages, where the same relative path
+    // means different files.
+    let source = std::fs::canonicalize(&file).map_err(Error::io(&file))?;
+    context
+        .index
+        .lock()
+        .await
+        .record(&format!("path+{}", source.display()), &digest, &wasm_sha256);
+    Ok(ResolvedModule {
+        path: context.cache.blob_path(&wasm_sha256),
+        reference: format!("path+{}", path.display()),
+        digest,
+        wasm_sha256,
+        annotations: Default::default(),
+    })
+}
+
+/// Downloads a module into the cache, unless the locked module is already
+/// there.
+pub(crate) async fn sync_url(
+    name: &str,
+    url: &str,
+    sha256: Option<&str>,
+    context: &SyncContext,
+    locked: Option<LockedContract>,
+) -> Result<(PathBuf, LockedContract), Error> {
+    let locked = locked.filter(|locked| {
+        locked.reference == url && sha256.is_none_or(|sha256| sha256 == locked.wasm_sha256)
+    });
+    if let Some(locked) = &locked {
+        if let Some(vendored) = context.vendored(&locked.name, &locked.wasm_sha256)? {
+            return Ok((vendored, locked.clone()));
+        }
+        if context.cache.has_blob(&locked.wasm_sha256) {
+            // Keep the module from looking unused to `prune --unused-for`.
+            context
+                .index
+                .lock()
+                .await
+                .record(url, &locked.digest, &locked.wasm_sha256);
+            return Ok((context.cache.blob_path(&locked.wasm_sha256), locked.clone()));
+        }
+    }
+    if context.offline {
+        return Err(Error::Offline {
+            reference: url.to_string(),
+        });
+    }
+
+    throw_warning!("Contract [{name}] could not be found in cache, downloading {url}...");
+    let download = |source| Error::Http {
+        url: url.to_string(),
+        source,
+    };
+    let data = reqwest::get(url)
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(download)?
+        .bytes()
+        .await
+        .map_err(download)?;
+    let found = hex::encode(Sha256::digest(&data));
+    if let Some(locked) = &locked {
+        if found != locked.wasm_sha256 {
+            return Err(Error::LockMismatch {
+                reference: url.to_string(),
+                expected: format!("sha256 {}", locked.wasm_sha256),
+                found: format!("sha256 {found}"),
+            });
+        }
+    }
+    if let Some(expected) = sha256 {
+        if found != expected {
+            return Err(Error::DigestMismatch {
+                reference: url.to_string(),
+                expected: expected.to_string(),
+                found,
+            });
+        }
+    }
+
+    crate::validate_contract(url, &data)?;
+    let wasm_sha256 = context.cache.write_blob(&data)?;
+    throw_warning!("Contract [{name}] downloaded {} bytes", data.len());
+    let digest = format!("sha256:{wasm_sha256}");
+    context
+        .index
+        .lock()
+        .await
+        .record(url, &digest, &wasm_sha256);
+    let locked = LockedContract {
+        name: name.to_string(),
+        reference: url.to_string(),
+        version: None,
+        required_by: Vec::new(),
+        digest,
+        wasm_sha256,
+        layer: None,
+        annotations: Default::default(),
+        layers: Vec::new(),
+    };
+    Ok((context.cache.blob_path(&locked.wasm_sha256), locked))
+}
@@ -0,0 +1,76 @@
+//! Builders of small contract modules for tests, enabled by the
+//! `test-support` feature.
+
+use stellar_xdr::curr::{
+    Limits, ScEnvMetaEntry, ScMetaEntry, ScMetaV0, ScSpecEntry, ScSpecFunctionInputV0,
+    ScSpecFunctionV0, ScSpecTypeDef, WriteXdr,
+};
+
+use crate::wasm::SDK_VERSION_KEY;
+pub use crate::wasm::{ENV_META_SECTION, META_SECTION, SPEC_SECTION};
+
+/// Builds a module made of the given custom sections only.
+pub fn module(sections: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    for (name, data) in sections {
+        let mut content = vec![name.len() as u8];
+        content.extend_from_slice(name.as_bytes());
+        content.extend_from_slice(data);
+        wasm.push(0);
+        let mut size = content.len();
+        loop {
+            let byte = (size & 0x7f) as u8;
+            size >>= 7;
+            if size == 0 {
+                wasm.push(byte);
+                break;
+            }
+            wasm.push(byte | 0x80);
+        }
+        wasm.extend(content);
+    }
+    wasm
+}
+
+/// The spec of a contract with a single `vote(proposal: u32) -> bool`.
+pub fn spec() -> Vec<u8> {
+    let entry = ScSpecEntry::FunctionV0(ScSpecFunctionV0 {
+        doc: Default::default(),
+        name: "vote".try_into().unwrap(),
+        inputs: vec![ScSpecFunctionInputV0 {
+            doc: Default::default(),
+            name: "proposal".try_into().unwrap(),
+            type_: ScSpecTypeDef::U32,
+        }]
+        .try_into()
+        .unwrap(),
+        outputs: vec![ScSpecTypeDef::Bool].try_into().unwrap(),
+    });
+    entry.to_xdr(Limits::none()).unwrap()
+}
+
+/// The `contractenvmetav0` content of a contract built for
+/// `interface_version`, the protocol in the high 32 bits.
+pub fn env_meta(interface_version: u64) -> Vec<u8> {
+    ScEnvMetaEntry::ScEnvMetaKindInterfaceVersion(interface_version)
+        .to_xdr(Limits::none())
+        .unwrap()
+}
+
+/// The `contractmetav0` content of a contract built with `sdk_version`.
+pub fn sdk_meta(sdk_version: &str) -> Vec<u8> {
+    ScMetaEntry::ScMetaV0(ScMetaV0 {
+        key: SDK_VERSION_KEY.try_into().unwrap(),
+        val: sdk_version.try_into().unwrap(),
+    })
+    .to_xdr(Limits::none())
+    .unwrap()
+}
+
+/// A contract with [`spec`], built for protocol 20.
+pub fn contract() -> Vec<u8> {
+    module(&[
+        (SPEC_SECTION, &spec()),
+        (ENV_META_SECTION, &env_meta(20 << 32)),
+    ])
+}
@@ -0,0 +1,144 @@
+//! Resolution of `version` requirements against the tags of a repository.
+
+use oci_distribution::{secrets::RegistryAuth, Client, Reference};
+use semver::{Version, VersionReq};
+use std::collections::HashSet;
+
+use crate::Error;
+
+/// Number of tags requested per page of the tags API. Registries may answer
+/// with fewer.
+const PAGE_SIZE: usize = 100;
+
+/// Most pages requested, in case a registry keeps answering with new tags.
+const MAX_PAGES: usize = 100;
+
+/// Lists every tag of the repository of `reference`, following pagination.
+///
+/// The tags API gives no reliable way to tell the last page, so pages are
+/// requested until one is empty or adds no new tags.
+pub(crate) async fn list_tags(
+    client: &mut Client,
+    auth: &RegistryAuth,
+    reference: &Reference,
+) -> Result<Vec<String>, Error> {
+    let mut tags = Tags::default();
+    for _ in 0..MAX_PAGES {
+        let page = client
+            .list_tags(reference, auth, Some(PAGE_SIZE), tags.last())
+            .await
+            .map_err(Error::registry(reference))?;
+        if !tags.add_page(page.tags) {
+            return Ok(tags.tags);
+        }
+    }
+    throw_warning!(
+        "{}/{}: stopped listing tags after {MAX_PAGES} pages, later versions may be missed",
+        reference.registry(),
+        reference.repository()
+    );
+    Ok(tags.tags)
+}
+
+/// The tags listed so far, in the order of the pages.
+#[derive(Debug, Default)]
+struct Tags {
+    tags: Vec<String>,
+    seen: HashSet<String>,
+}
+
+impl Tags {
+    /// The tag to continue the listing after.
+    fn last(&self) -> Option<&str> {
+        self.tags.last().map(String::as_str)
+    }
+
+    /// Adds the tags of a page, returning whether any was new.
+    fn add_page(&mut self, page: Vec<String>) -> bool {
+        let count = self.tags.len();
+        for tag in page {
+            if self.seen.insert(tag.clone()) {
+                self.tags.push(tag);
+            }
+        }
+        self.tags.len() > count
+    }
+}
+
+/// The version a tag names, accepting an optional `v` prefix as in `v0.2.0`.
+pub(crate) fn tag_version(tag: &str) -> Option<Version> {
+    Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok()
+}
+
+/// Picks the tag with the highest version matching `requirement`.
+///
+/// Tags that are not versions, such as `latest`, are ignored.
+pub(crate) fn select_tag<'a>(
+    tags: impl IntoIterator<Item = &'a str>,
+    requirement: &VersionReq,
+) -> Option<&'a str> {
+    tags.into_iter()
+        .filter_map(|tag| Some((tag_version(tag)?, tag)))
+        .filter(|(version, _)| requirement.matches(version))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, tag)| tag)
+}
+
+/// Resolves a version requirement to the tag of the highest matching version
+/// in the repository of `reference`.
+pub(crate) async fn resolve_tag(
+    client: &mut Client,
+    auth: &RegistryAuth,
+    reference: &Reference,
+    requirement: &VersionReq,
+) -> Result<String, Error> {
+    let tags = list_tags(client, auth, reference).await?;
+    select_tag(tags.iter().map(String::as_str), requirement)
+        .map(str::to_string)
+        .ok_or_else(|| Error::NoMatchingVersion {
+            reference: format!("{}/{}", reference.registry(), reference.repository()),
+            requirement: requirement.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tag_pages() {
+        let page = |tags: &[&str]| tags.iter().map(|tag| tag.to_string()).collect();
+        let mut tags = Tags::default();
+        assert_eq!(tags.last(), None);
+        // A registry capping pages below `PAGE_SIZE` still gets asked again.
+        assert!(tags.add_page(page(&["v0.1.0", "v0.2.0"])));
+        assert_eq!(tags.last(), Some("v0.2.0"));
+        assert!(tags.add_page(page(&["v0.2.0", "v0.3.0"])));
+        assert_eq!(tags.tags, ["v0.1.0", "v0.2.0", "v0.3.0"]);
+        // A registry ignoring `last` answers with the same page again.
+        assert!(!tags.add_page(page(&["v0.1.0", "v0.2.0"])));
+        assert!(!tags.add_page(Vec::new()));
+        assert_eq!(tags.tags, ["v0.1.0", "v0.2.0", "v0.3.0"]);
+    }
+
+    #[test]
+    fn test_select_tag() {
+        let tags = [
+            "latest",
+            "v0.1.0",
+            "v0.2.0",
+            "0.2.3",
+            "v0.2.10",
+            "v0.3.0-rc.1",
+            "v0.3.0",
+            "v1.0.0",
+        ];
+        let select = |requirement: &str| select_tag(tags, &requirement.parse().unwrap());
+
+        assert_eq!(select("^0.2"), Some("v0.2.10"));
+        assert_eq!(select("~0.2.0"), Some("v0.2.10"));
+        assert_eq!(select(">=0.3, <2"), Some("v1.0.0"));
+        assert_eq!(select("=0.3.0-rc.1"), Some("v0.3.0-rc.1"));
+        assert_eq!(select("^2"), None);
+    }
+}
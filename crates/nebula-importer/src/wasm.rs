@@ -0,0 +1,285 @@
+//! Just enough of the Wasm binary format to read the custom sections of a
+//! Soroban contract.
+
+use sha2::{Digest, Sha256};
+use std::io::Cursor;
+use stellar_xdr::curr::{Limited, Limits, ReadXdr, ScEnvMetaEntry, ScMetaEntry, ScSpecEntry};
+
+use crate::InterfaceVersion;
+
+/// Custom section holding the XDR encoded interface of a contract.
+pub const SPEC_SECTION: &str = "contractspecv0";
+/// Custom section holding the environment interface a contract was built for.
+pub const ENV_META_SECTION: &str = "contractenvmetav0";
+/// Custom section holding key-value metadata, like the SDK version.
+pub const META_SECTION: &str = "contractmetav0";
+/// Key of the SDK version in [`META_SECTION`].
+pub(crate) const SDK_VERSION_KEY: &str = "rssdkver";
+
+const MAGIC: &[u8] = b"\0asm";
+const VERSION: &[u8] = &[1, 0, 0, 0];
+
+/// Why a module could not be read as a Soroban contract.
+#[derive(Debug, thiserror::Error)]
+pub enum WasmError {
+    #[error("not a Wasm module")]
+    NotWasm,
+    #[error("malformed Wasm module")]
+    Malformed,
+    #[error("missing `{0}` custom section")]
+    MissingSection(&'static str),
+    #[error("invalid `{section}` custom section")]
+    InvalidSection {
+        section: &'static str,
+        #[source]
+        source: stellar_xdr::curr::Error,
+    },
+}
+
+/// Lists the custom sections of a module as `(name, content)` pairs, in the
+/// order they appear.
+pub(crate) fn custom_sections(wasm: &[u8]) -> Result<Vec<(&str, &[u8])>, WasmError> {
+    let rest = wasm.strip_prefix(MAGIC).ok_or(WasmError::NotWasm)?;
+    let mut rest = rest.strip_prefix(VERSION).ok_or(WasmError::NotWasm)?;
+    let mut sections = Vec::new();
+    while let Some((&id, tail)) = rest.split_first() {
+        let (size, tail) = read_u32(tail)?;
+        let (content, tail) = split(tail, size)?;
+        rest = tail;
+        if id == 0 {
+            let (len, content) = read_u32(content)?;
+            let (name, data) = split(content, len)?;
+            let name = std::str::from_utf8(name).map_err(|_| WasmError::Malformed)?;
+            sections.push((name, data));
+        }
+    }
+    Ok(sections)
+}
+
+/// Checks that a module looks like a Soroban contract: a Wasm module with
+/// both the spec and the environment metadata sections.
+pub(crate) fn validate_contract(wasm: &[u8]) -> Result<(), WasmError> {
+    let sections = custom_sections(wasm)?;
+    for required in [SPEC_SECTION, ENV_META_SECTION] {
+        if !sections.iter().any(|(name, _)| *name == required) {
+            return Err(WasmError::MissingSection(required));
+        }
+    }
+    Ok(())
+}
+
+/// The content of the first custom section called `name`.
+pub(crate) fn custom_section<'a>(
+    wasm: &'a [u8],
+    name: &str,
+) -> Result<Option<&'a [u8]>, WasmError> {
+    Ok(custom_sections(wasm)?
+        .into_iter()
+        .find(|(section, _)| *section == name)
+        .map(|(_, data)| data))
+}
+
+/// Decodes the interface of a contract from its `contractspecv0` section.
+pub(crate) fn contract_spec(wasm: &[u8]) -> Result<Vec<ScSpecEntry>, WasmError> {
+    let spec =
+        custom_section(wasm, SPEC_SECTION)?.ok_or(WasmError::MissingSection(SPEC_SECTION))?;
+    let limits = Limits {
+        depth: 500,
+        len: spec.len(),
+    };
+    ScSpecEntry::read_xdr_iter(&mut Limited::new(Cursor::new(spec), limits))
+        .collect::<Result<_, _>>()
+        .map_err(|source| WasmError::InvalidSection {
+            section: SPEC_SECTION,
+            source,
+        })
+}
+
+/// Reads the environment interface version from `contractenvmetav0`.
+pub(crate) fn interface_version(wasm: &[u8]) -> Result<InterfaceVersion, WasmError> {
+    let meta = custom_section(wasm, ENV_META_SECTION)?
+        .ok_or(WasmError::MissingSection(ENV_META_SECTION))?;
+    let limits = Limits {
+        depth: 500,
+        len: meta.len(),
+    };
+    let invalid = |source| WasmError::InvalidSection {
+        section: ENV_META_SECTION,
+        source,
+    };
+    let mut reader = Limited::new(Cursor::new(meta), limits);
+    let mut entries = ScEnvMetaEntry::read_xdr_iter(&mut reader);
+    match entries.next() {
+        Some(Ok(ScEnvMetaEntry::ScEnvMetaKindInterfaceVersion(version))) => Ok(version.into()),
+        Some(Err(source)) => Err(invalid(source)),
+        None => Err(invalid(stellar_xdr::curr::Error::Invalid)),
+    }
+}
+
+/// What a contract module says about itself, read from its custom sections.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractMetadata {
+    /// The functions of the contract spec, in the order of the spec.
+    pub functions: Vec<String>,
+    /// Hex encoded sha256 of the `contractspecv0` section.
+    pub spec_sha256: String,
+    pub interface_version: InterfaceVersion,
+    /// The SDK the contract was built with, e.g. `20.5.0#<commit>`, when the
+    /// contract records it.
+    pub sdk_version: Option<String>,
+}
+
+/// Checks that `wasm` is a Soroban contract and reads its metadata.
+pub fn contract_metadata(wasm: &[u8]) -> Result<ContractMetadata, WasmError> {
+    validate_contract(wasm)?;
+    let functions = contract_spec(wasm)?
+        .into_iter()
+        .filter_map(|entry| match entry {
+            ScSpecEntry::FunctionV0(function) => Some(function.name.to_utf8_string_lossy()),
+            _ => None,
+        })
+        .collect();
+    let spec = custom_section(wasm, SPEC_SECTION)?.unwrap_or_default();
+    Ok(ContractMetadata {
+        functions,
+        spec_sha256: hex::encode(Sha256::digest(spec)),
+        interface_version: interface_version(wasm)?,
+        sdk_version: sdk_version(wasm)?,
+    })
+}
+
+/// Reads the SDK version from `contractmetav0`, if the section and the entry
+/// are there.
+fn sdk_version(wasm: &[u8]) -> Result<Option<String>, WasmError> {
+    let Some(meta) = custom_section(wasm, META_SECTION)? else {
+        return Ok(None);
+    };
+    let limits = Limits {
+        depth: 500,
+        len: meta.len(),
+    };
+    for entry in ScMetaEntry::read_xdr_iter(&mut Limited::new(Cursor::new(meta), limits)) {
+        let ScMetaEntry::ScMetaV0(entry) = entry.map_err(|source| WasmError::InvalidSection {
+            section: META_SECTION,
+            source,
+        })?;
+        if entry.key.to_utf8_string_lossy() == SDK_VERSION_KEY {
+            return Ok(Some(entry.val.to_utf8_string_lossy()));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads an unsigned LEB128 encoded `u32`.
+fn read_u32(data: &[u8]) -> Result<(usize, &[u8]), WasmError> {
+    let mut value = 0u32;
+    for (index, byte) in data.iter().take(5).enumerate() {
+        value |= u32::from(byte & 0x7f) << (7 * index);
+        if byte & 0x80 == 0 {
+            return Ok((value as usize, &data[index + 1..]));
+        }
+    }
+    Err(WasmError::Malformed)
+}
+
+fn split(data: &[u8], len: usize) -> Result<(&[u8], &[u8]), WasmError> {
+    data.split_at_checked(len).ok_or(WasmError::Malformed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{env_meta, module, sdk_meta, spec};
+
+    #[test]
+    fn test_custom_sections() {
+        let padding = vec![0; 200];
+        let wasm = module(&[("name", b"voting"), ("padding", &padding)]);
+        let sections = custom_sections(&wasm).unwrap();
+        assert_eq!(
+            sections,
+            vec![("name", &b"voting"[..]), ("padding", &padding[..])]
+        );
+
+        assert!(matches!(custom_sections(b"\0asn"), Err(WasmError::NotWasm)));
+        assert!(matches!(
+            custom_sections(&wasm[..wasm.len() - 1]),
+            Err(WasmError::Malformed)
+        ));
+    }
+
+    #[test]
+    fn test_validate_contract() {
+        let spec = spec();
+        assert!(
+            validate_contract(&module(&[(SPEC_SECTION, &spec), (ENV_META_SECTION, &[])])).is_ok()
+        );
+        assert!(matches!(
+            validate_contract(&module(&[(SPEC_SECTION, &spec)])),
+            Err(WasmError::MissingSection(ENV_META_SECTION))
+        ));
+        assert!(matches!(
+            validate_contract(b"{\"schemaVersion\": 2}"),
+            Err(WasmError::NotWasm)
+        ));
+    }
+
+    #[test]
+    fn test_interface_version() {
+        let meta = env_meta((20 << 32) | 1);
+        let wasm = module(&[(ENV_META_SECTION, &meta)]);
+        assert_eq!(
+            interface_version(&wasm).unwrap(),
+            InterfaceVersion {
+                protocol: 20,
+                pre_release: 1
+            }
+        );
+        assert!(matches!(
+            interface_version(&module(&[(ENV_META_SECTION, &[])])),
+            Err(WasmError::InvalidSection { .. })
+        ));
+    }
+
+    #[test]
+    fn test_contract_metadata() {
+        let spec = spec();
+        let env_meta = env_meta(20 << 32);
+        let meta = sdk_meta("20.5.0#abc");
+        let wasm = module(&[
+            (SPEC_SECTION, &spec),
+            (ENV_META_SECTION, &env_meta),
+            (META_SECTION, &meta),
+        ]);
+        let metadata = contract_metadata(&wasm).unwrap();
+        assert_eq!(metadata.functions, ["vote"]);
+        assert_eq!(metadata.spec_sha256, hex::encode(Sha256::digest(&spec)));
+        assert_eq!(metadata.interface_version.protocol, 20);
+        assert_eq!(metadata.sdk_version.as_deref(), Some("20.5.0#abc"));
+
+        let wasm = module(&[(SPEC_SECTION, &spec), (ENV_META_SECTION, &env_meta)]);
+        assert_eq!(contract_metadata(&wasm).unwrap().sdk_version, None);
+        assert!(matches!(
+            contract_metadata(&module(&[(SPEC_SECTION, &spec)])),
+            Err(WasmError::MissingSection(ENV_META_SECTION))
+        ));
+    }
+
+    #[test]
+    fn test_contract_spec() {
+        let wasm = module(&[(SPEC_SECTION, &spec())]);
+        let entries = contract_spec(&wasm).unwrap();
+        assert!(
+            matches!(&entries[..], [ScSpecEntry::FunctionV0(f)] if f.name.to_utf8_string_lossy() == "vote")
+        );
+
+        assert!(matches!(
+            contract_spec(&module(&[])),
+            Err(WasmError::MissingSection(SPEC_SECTION))
+        ));
+        assert!(matches!(
+            contract_spec(&module(&[(SPEC_SECTION, &[0, 0, 0])])),
+            Err(WasmError::InvalidSection { .. })
+        ));
+    }
+}
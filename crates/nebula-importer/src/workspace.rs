@@ -0,0 +1,56 @@
+//! Shared configuration in `[workspace.metadata.nebula]` of the workspace
+//! root manifest.
+
+use figment::{
+    providers::{Format, Toml},
+    Figment,
+};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::{Config, Error};
+
+#[derive(Debug, Deserialize, Default)]
+struct WorkspaceManifest {
+    workspace: Option<Workspace>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct Workspace {
+    #[serde(default)]
+    metadata: WorkspaceMetadata,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct WorkspaceMetadata {
+    #[serde(default)]
+    nebula: Config,
+}
+
+/// Finds the manifest of the workspace containing `dir`, which may be the
+/// manifest in `dir` itself.
+pub(crate) fn find_workspace_manifest(dir: &Path) -> Result<Option<PathBuf>, Error> {
+    let dir = std::fs::canonicalize(dir).map_err(Error::io(dir))?;
+    for ancestor in dir.ancestors() {
+        let manifest = ancestor.join("Cargo.toml");
+        if !manifest.is_file() {
+            continue;
+        }
+        let parsed: WorkspaceManifest = Figment::new().merge(Toml::file(&manifest)).extract()?;
+        if parsed.workspace.is_some() {
+            return Ok(Some(manifest));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads `[workspace.metadata.nebula]`, with relative paths resolved against
+/// the workspace root.
+pub(crate) fn load_workspace_config(manifest: &Path) -> Result<Config, Error> {
+    let parsed: WorkspaceManifest = Figment::new().merge(Toml::file(manifest)).extract()?;
+    let config = parsed.workspace.unwrap_or_default().metadata.nebula;
+    Ok(match manifest.parent() {
+        Some(root) => config.rebase(root),
+        None => config,
+    })
+}
@@ -0,0 +1,171 @@
+//! Compiles the bindings the importer generates against `soroban-sdk`.
+//!
+//! This runs in a process of its own, as the generated code is compiled with
+//! `OUT_DIR` set the way a build script would see it.
+
+use nebula_importer::{test_support, Importer};
+use stellar_xdr::curr::{
+    Limits, ScSpecEntry, ScSpecFunctionInputV0, ScSpecFunctionV0, ScSpecTypeDef,
+    ScSpecTypeResult, ScSpecTypeUdt, ScSpecUdtEnumCaseV0, ScSpecUdtEnumV0,
+    ScSpecUdtErrorEnumCaseV0, ScSpecUdtErrorEnumV0, ScSpecUdtStructFieldV0, ScSpecUdtStructV0,
+    ScSpecUdtUnionCaseTupleV0, ScSpecUdtUnionCaseV0, ScSpecUdtUnionCaseVoidV0, ScSpecUdtUnionV0,
+    WriteXdr,
+};
+
+/// A contract with a struct, a union, an integer enum and an error enum:
+/// `vote(proposal: Proposal, ballot: Ballot) -> Result<Status, VotingError>`.
+fn spec() -> Vec<u8> {
+    let udt = |name: &str| {
+        ScSpecTypeDef::Udt(ScSpecTypeUdt {
+            name: name.try_into().unwrap(),
+        })
+    };
+    let entries = [
+        ScSpecEntry::UdtStructV0(ScSpecUdtStructV0 {
+            doc: Default::default(),
+            lib: Default::default(),
+            name: "Proposal".try_into().unwrap(),
+            fields: vec![ScSpecUdtStructFieldV0 {
+                doc: Default::default(),
+                name: "id".try_into().unwrap(),
+                type_: ScSpecTypeDef::U32,
+            }]
+            .try_into()
+            .unwrap(),
+        }),
+        ScSpecEntry::UdtUnionV0(ScSpecUdtUnionV0 {
+            doc: Default::default(),
+            lib: Default::default(),
+            name: "Ballot".try_into().unwrap(),
+            cases: vec![
+                ScSpecUdtUnionCaseV0::VoidV0(ScSpecUdtUnionCaseVoidV0 {
+                    doc: Default::default(),
+                    name: "Abstain".try_into().unwrap(),
+                }),
+                ScSpecUdtUnionCaseV0::TupleV0(ScSpecUdtUnionCaseTupleV0 {
+                    doc: Default::default(),
+                    name: "Delegate".try_into().unwrap(),
+                    type_: vec![ScSpecTypeDef::Address].try_into().unwrap(),
+                }),
+            ]
+            .try_into()
+            .unwrap(),
+        }),
+        ScSpecEntry::UdtEnumV0(ScSpecUdtEnumV0 {
+            doc: Default::default(),
+            lib: Default::default(),
+            name: "Status".try_into().unwrap(),
+            cases: vec![ScSpecUdtEnumCaseV0 {
+                doc: Default::default(),
+                name: "Open".try_into().unwrap(),
+                value: 0,
+            }]
+            .try_into()
+            .unwrap(),
+        }),
+        ScSpecEntry::UdtErrorEnumV0(ScSpecUdtErrorEnumV0 {
+            doc: Default::default(),
+            lib: Default::default(),
+            name: "VotingError".try_into().unwrap(),
+            cases: vec![ScSpecUdtErrorEnumCaseV0 {
+                doc: Default::default(),
+                name: "AlreadyVoted".try_into().unwrap(),
+                value: 1,
+            }]
+            .try_into()
+            .unwrap(),
+        }),
+        ScSpecEntry::FunctionV0(ScSpecFunctionV0 {
+            doc: Default::default(),
+            name: "vote".try_into().unwrap(),
+            inputs: vec![
+                ScSpecFunctionInputV0 {
+                    doc: Default::default(),
+                    name: "proposal".try_into().unwrap(),
+                    type_: udt("Proposal"),
+                },
+                ScSpecFunctionInputV0 {
+                    doc: Default::default(),
+                    name: "ballot".try_into().unwrap(),
+                    type_: udt("Ballot"),
+                },
+            ]
+            .try_into()
+            .unwrap(),
+            outputs: vec![ScSpecTypeDef::Result(Box::new(ScSpecTypeResult {
+                ok_type: Box::new(udt("Status")),
+                error_type: Box::new(udt("VotingError")),
+            }))]
+            .try_into()
+            .unwrap(),
+        }),
+    ];
+    entries
+        .iter()
+        .flat_map(|entry| entry.to_xdr(Limits::none()).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_bindings_compile_with_custom_derives() {
+    let dir = tempfile::tempdir().unwrap();
+    let wasm = test_support::module(&[
+        (test_support::SPEC_SECTION, &spec()),
+        (test_support::ENV_META_SECTION, &test_support::env_meta(20 << 32)),
+    ]);
+    std::fs::write(dir.path().join("voting.wasm"), wasm).unwrap();
+    // The derives of the README example, without the `Copy` soroban-sdk
+    // needs on integer and error enums.
+    std::fs::write(
+        dir.path().join("Cargo.toml"),
+        r#"
+        [package]
+        name = "governance"
+
+        [package.metadata.nebula.imports]
+        voting = { path = "voting.wasm" }
+
+        [package.metadata.nebula.bindings]
+        derive = ["Debug", "Clone", "PartialEq"]
+        "#,
+    )
+    .unwrap();
+    let out_dir = dir.path().join("out");
+    Importer::new()
+        .manifest(dir.path().join("Cargo.toml"))
+        .cache(dir.path().join("cache"))
+        .out_dir(&out_dir)
+        .blocking_sync()
+        .unwrap();
+
+    let contract = dir.path().join("contract.rs");
+    std::fs::write(
+        &contract,
+        r#"
+        mod contracts {
+            include!(concat!(env!("OUT_DIR"), "/nebula_importer.rs"));
+        }
+
+        use contracts::voting::{Ballot, Client, Proposal, Status, VotingError};
+
+        pub fn vote(client: &Client, proposal: &Proposal) -> Option<Status> {
+            let status = client.try_vote(proposal, &Ballot::Abstain).ok()?.ok()?;
+            assert_eq!(proposal.clone(), Proposal { id: proposal.id });
+            Some(status)
+        }
+
+        fn main() {
+            let error = VotingError::AlreadyVoted;
+            let copied = error;
+            assert_eq!(error, copied);
+        }
+        "#,
+    )
+    .unwrap();
+    // Only this test runs in this process, nothing else reads `OUT_DIR`.
+    std::env::set_var("OUT_DIR", &out_dir);
+    let cases = trybuild::TestCases::new();
+    cases.pass(&contract);
+    // The cases are compiled when dropped, while the directory still exists.
+    drop(cases);
+}
@@ -0,0 +1,52 @@
+use nebula_importer::WasmError;
+use oci_distribution::errors::OciDistributionError;
+use std::path::PathBuf;
+
+/// Everything that can go wrong while publishing a contract.
+#[derive(Debug, thiserror::Error)]
+pub enum PublishError {
+    #[error("could not read {path}")]
+    Io {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("{path} is not a Soroban contract")]
+    InvalidModule {
+        path: PathBuf,
+        #[source]
+        source: WasmError,
+    },
+    #[error("could not load lockfile {path}")]
+    Lockfile {
+        path: PathBuf,
+        #[source]
+        source: nebula_importer::Error,
+    },
+    #[error("invalid image reference `{reference}`")]
+    InvalidReference {
+        reference: String,
+        #[source]
+        source: oci_distribution::ParseError,
+    },
+    #[error("authentication to the registry failed for {reference}: {message}")]
+    Auth { reference: String, message: String },
+    #[error("could not push to {reference}")]
+    Registry {
+        reference: String,
+        #[source]
+        source: OciDistributionError,
+    },
+}
+
+impl PublishError {
+    /// Tells authentication failures apart from other registry errors, like
+    /// the importer does.
+    pub(crate) fn registry(reference: impl ToString) -> impl FnOnce(OciDistributionError) -> Self {
+        let reference = reference.to_string();
+        move |source| match nebula_importer::auth_failure(&source) {
+            Some(message) => PublishError::Auth { reference, message },
+            None => PublishError::Registry { reference, source },
+        }
+    }
+}
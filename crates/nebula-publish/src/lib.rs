@@ -1,22 +1,118 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, path::Path};
 
+use nebula_importer::WasmError;
 use oci_distribution::{
     client::{Config, ImageLayer},
     manifest,
     secrets::RegistryAuth,
     Client, Reference,
 };
+use sha2::{Digest, Sha256};
 
+mod error;
+
+pub use error::PublishError;
+
+/// Annotation listing the functions of the contract, separated by commas.
+pub const FUNCTIONS_ANNOTATION: &str = "co.eiger.nebula.functions";
+/// Annotation holding the hex encoded sha256 of the contract spec.
+pub const SPEC_SHA256_ANNOTATION: &str = "co.eiger.nebula.spec.sha256";
+/// Annotation holding the ledger protocol of the environment interface the
+/// contract was built for.
+pub const INTERFACE_VERSION_ANNOTATION: &str = "co.eiger.nebula.interface";
+/// Annotation holding the pre-release of the environment interface, only set
+/// for contracts built with a pre-release SDK.
+pub const INTERFACE_PRE_RELEASE_ANNOTATION: &str = "co.eiger.nebula.interface.pre_release";
+/// Annotation holding the hex encoded sha256 of the module.
+pub const WASM_SHA256_ANNOTATION: &str = "co.eiger.nebula.wasm.sha256";
+/// Annotation holding the SDK version the contract was built with.
+pub const SDK_VERSION_ANNOTATION: &str = "co.eiger.nebula.sdk";
+
+/// What was pushed, so tools can record the published artifact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushOutcome {
+    /// Where the registry stored the manifest.
+    pub manifest_url: String,
+    /// Digest of the manifest, to pull exactly this artifact by digest, if
+    /// the registry's manifest URL names it.
+    pub manifest_digest: Option<String>,
+    pub config_digest: String,
+    /// Digest of the Wasm layer, the sha256 of the module.
+    pub layer_digest: String,
+}
+
+/// The digest of a pushed manifest, from the `Location` URL registries answer
+/// with, e.g. `https://ghcr.io/v2/<name>/manifests/sha256:<hex>`. Registries
+/// answering with the tag URL do not name it.
+fn manifest_url_digest(manifest_url: &str) -> Option<String> {
+    let (_, last) = manifest_url.rsplit_once("/manifests/")?;
+    let hex = last.strip_prefix("sha256:")?;
+    (hex.len() == 64 && hex.bytes().all(|byte| byte.is_ascii_hexdigit())).then(|| last.to_string())
+}
+
+/// The annotations describing a contract module, so consumers can inspect an
+/// artifact without pulling it. Fails if the module is not a Soroban contract.
+pub fn contract_annotations(wasm: &[u8]) -> Result<HashMap<String, String>, WasmError> {
+    let metadata = nebula_importer::contract_metadata(wasm)?;
+    let mut annotations = HashMap::from([
+        (
+            FUNCTIONS_ANNOTATION.to_string(),
+            metadata.functions.join(","),
+        ),
+        (SPEC_SHA256_ANNOTATION.to_string(), metadata.spec_sha256),
+        (
+            INTERFACE_VERSION_ANNOTATION.to_string(),
+            metadata.interface_version.protocol.to_string(),
+        ),
+        (
+            WASM_SHA256_ANNOTATION.to_string(),
+            hex::encode(Sha256::digest(wasm)),
+        ),
+    ]);
+    if metadata.interface_version.pre_release != 0 {
+        annotations.insert(
+            INTERFACE_PRE_RELEASE_ANNOTATION.to_string(),
+            metadata.interface_version.pre_release.to_string(),
+        );
+    }
+    if let Some(sdk_version) = metadata.sdk_version {
+        annotations.insert(SDK_VERSION_ANNOTATION.to_string(), sdk_version);
+    }
+    Ok(annotations)
+}
+
+/// The given annotations with the [`contract_annotations`] of the module.
+fn manifest_annotations(
+    wasm: &[u8],
+    given: Option<HashMap<String, String>>,
+) -> Result<HashMap<String, String>, WasmError> {
+    // Consumers rely on the derived annotations describing the module, so
+    // given ones cannot override them.
+    let mut annotations = given.unwrap_or_default();
+    annotations.extend(contract_annotations(wasm)?);
+    Ok(annotations)
+}
+
+/// Pushes a Soroban contract as a single layer artifact, annotated with
+/// [`contract_annotations`], which replace given annotations with the same key.
 pub async fn push_wasm(
     client: &mut Client,
     auth: &RegistryAuth,
     reference: &Reference,
-    module: &PathBuf,
+    module: &Path,
     annotations: Option<HashMap<String, String>>,
-) {
+) -> Result<PushOutcome, PublishError> {
     let data = tokio::fs::read(module)
         .await
-        .expect("Cannot read Wasm module from disk");
+        .map_err(|source| PublishError::Io {
+            path: module.to_path_buf(),
+            source,
+        })?;
+    let manifest_annotations =
+        manifest_annotations(&data, annotations).map_err(|source| PublishError::InvalidModule {
+            path: module.to_path_buf(),
+            source,
+        })?;
 
     let layers = vec![ImageLayer::new(
         data,
@@ -30,13 +126,156 @@ pub async fn push_wasm(
         annotations: None,
     };
 
-    let image_manifest = manifest::OciImageManifest::build(&layers, &config, annotations);
+    let image_manifest =
+        manifest::OciImageManifest::build(&layers, &config, Some(manifest_annotations));
+    let config_digest = image_manifest.config.digest.clone();
+    let layer_digest = image_manifest.layers[0].digest.clone();
 
     let response = client
-        .push(&reference, &layers, config, &auth, Some(image_manifest))
+        .push(reference, &layers, config, auth, Some(image_manifest))
         .await
-        .map(|push_response| push_response.manifest_url)
-        .expect("Cannot push Wasm module");
+        .map_err(PublishError::registry(reference))?;
+    // Asking the registry again could see a later push to the same tag.
+    let manifest_digest = manifest_url_digest(&response.manifest_url);
+
+    Ok(PushOutcome {
+        manifest_url: response.manifest_url,
+        manifest_digest,
+        config_digest,
+        layer_digest,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nebula_importer::test_support::{
+        env_meta, module, sdk_meta, spec, ENV_META_SECTION, META_SECTION, SPEC_SECTION,
+    };
+    use oci_distribution::{client::ClientConfig, errors::OciDistributionError};
+
+    #[test]
+    fn test_contract_annotations() {
+        let spec = spec();
+        let wasm = module(&[
+            (SPEC_SECTION, &spec),
+            (ENV_META_SECTION, &env_meta(20 << 32)),
+            (META_SECTION, &sdk_meta("20.5.0#abc")),
+        ]);
+        let annotations = contract_annotations(&wasm).unwrap();
+        assert_eq!(annotations[FUNCTIONS_ANNOTATION], "vote");
+        assert_eq!(
+            annotations[SPEC_SHA256_ANNOTATION],
+            hex::encode(Sha256::digest(&spec))
+        );
+        assert_eq!(annotations[INTERFACE_VERSION_ANNOTATION], "20");
+        assert!(!annotations.contains_key(INTERFACE_PRE_RELEASE_ANNOTATION));
+        assert_eq!(
+            annotations[WASM_SHA256_ANNOTATION],
+            hex::encode(Sha256::digest(&wasm))
+        );
+        assert_eq!(annotations[SDK_VERSION_ANNOTATION], "20.5.0#abc");
+
+        let wasm = module(&[
+            (SPEC_SECTION, &spec),
+            (ENV_META_SECTION, &env_meta((21 << 32) | 3)),
+        ]);
+        let annotations = contract_annotations(&wasm).unwrap();
+        assert_eq!(annotations[INTERFACE_VERSION_ANNOTATION], "21");
+        assert_eq!(annotations[INTERFACE_PRE_RELEASE_ANNOTATION], "3");
+        assert!(!annotations.contains_key(SDK_VERSION_ANNOTATION));
+    }
+
+    #[test]
+    fn test_contract_annotations_reject_invalid_modules() {
+        assert!(matches!(
+            contract_annotations(b"{}"),
+            Err(WasmError::NotWasm)
+        ));
+        assert!(matches!(
+            contract_annotations(&module(&[(SPEC_SECTION, &spec())])),
+            Err(WasmError::MissingSection(ENV_META_SECTION))
+        ));
+    }
+
+    #[test]
+    fn test_manifest_annotations() {
+        let wasm = nebula_importer::test_support::contract();
+        let given = HashMap::from([
+            (INTERFACE_VERSION_ANNOTATION.to_string(), "19".to_string()),
+            (
+                "org.opencontainers.image.title".to_string(),
+                "voting.wasm".to_string(),
+            ),
+        ]);
+        let annotations = manifest_annotations(&wasm, Some(given)).unwrap();
+        assert_eq!(annotations[INTERFACE_VERSION_ANNOTATION], "20");
+        assert_eq!(annotations["org.opencontainers.image.title"], "voting.wasm");
+        assert_eq!(
+            manifest_annotations(&wasm, None).unwrap(),
+            contract_annotations(&wasm).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_push_rejects_invalid_modules() {
+        let dir = std::env::temp_dir().join(format!("nebula-publish-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let not_wasm = dir.join("not.wasm");
+        std::fs::write(&not_wasm, b"{}").unwrap();
+
+        let mut client = Client::new(ClientConfig::default());
+        let reference: Reference = "ghcr.io/eigerco/nebula/contracts/voting:v1"
+            .parse()
+            .unwrap();
+        let auth = RegistryAuth::Anonymous;
+        let missing = dir.join("missing.wasm");
+        let missing = push_wasm(&mut client, &auth, &reference, &missing, None);
+        assert!(matches!(missing.await, Err(PublishError::Io { .. })));
+        let not_wasm = push_wasm(&mut client, &auth, &reference, &not_wasm, None);
+        assert!(matches!(
+            not_wasm.await,
+            Err(PublishError::InvalidModule { .. })
+        ));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_registry_errors() {
+        let reference = "ghcr.io/eigerco/nebula/contracts/voting:v1";
+        assert!(matches!(
+            PublishError::registry(reference)(OciDistributionError::UnauthorizedError {
+                url: "https://ghcr.io/v2/".into(),
+            }),
+            PublishError::Auth { .. }
+        ));
+        assert!(matches!(
+            PublishError::registry(reference)(OciDistributionError::GenericError(None)),
+            PublishError::Registry { .. }
+        ));
+    }
 
-    println!("Wasm module successfully pushed {:?}", response);
+    #[test]
+    fn test_manifest_url_digest() {
+        let digest = format!("sha256:{}", "ab".repeat(32));
+        assert_eq!(
+            manifest_url_digest(&format!(
+                "https://ghcr.io/v2/eigerco/nebula/contracts/voting/manifests/{digest}"
+            )),
+            Some(digest.clone())
+        );
+        assert_eq!(
+            manifest_url_digest(&format!("/v2/voting/manifests/{digest}")),
+            Some(digest)
+        );
+        assert_eq!(
+            manifest_url_digest("https://ghcr.io/v2/eigerco/nebula/contracts/voting/manifests/v1"),
+            None
+        );
+        assert_eq!(
+            manifest_url_digest("https://ghcr.io/v2/voting/manifests/sha256:ab"),
+            None
+        );
+    }
 }
@@ -1,5 +1,6 @@
 use clap::Parser;
-use nebula_publish::push_wasm;
+use nebula_importer::{declare_imports, Lockfile, IMPORTS_ANNOTATION};
+use nebula_publish::{push_wasm, PublishError, PushOutcome};
 use oci_distribution::{annotations, secrets::RegistryAuth, Client, Reference};
 use std::{collections::HashMap, path::PathBuf};
 
@@ -12,6 +13,9 @@ struct Push {
     image: String,
     #[clap(long)]
     annotations: Vec<String>,
+    /// `Nebula.lock` of the contract, to declare its imports in the artifact
+    #[clap(long)]
+    lockfile: Option<PathBuf>,
     #[clap(long)]
     username: Option<String>,
     #[clap(long)]
@@ -19,7 +23,29 @@ struct Push {
 }
 #[tokio::main]
 pub async fn main() {
-    let config = Push::parse();
+    match push(Push::parse()).await {
+        Ok(outcome) => {
+            println!("Wasm module successfully pushed {}", outcome.manifest_url);
+            match &outcome.manifest_digest {
+                Some(digest) => println!("manifest: {digest}"),
+                None => println!("manifest: digest not reported by the registry"),
+            }
+            println!("config:   {}", outcome.config_digest);
+            println!("layer:    {}", outcome.layer_digest);
+        }
+        Err(error) => {
+            eprintln!("error: {error}");
+            let mut source = std::error::Error::source(&error);
+            while let Some(cause) = source {
+                eprintln!("  caused by: {cause}");
+                source = cause.source();
+            }
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn push(config: Push) -> Result<PushOutcome, PublishError> {
     let mut values: HashMap<String, String> = HashMap::new();
     for annotation in config.annotations {
         let tmp: Vec<_> = annotation.splitn(2, '=').collect();
@@ -27,24 +53,38 @@ pub async fn main() {
             values.insert(String::from(tmp[0]), String::from(tmp[1]));
         }
     }
-    if !values.contains_key(&annotations::ORG_OPENCONTAINERS_IMAGE_TITLE.to_string()) {
-        values.insert(
-            annotations::ORG_OPENCONTAINERS_IMAGE_TITLE.to_string(),
-            config.module.to_str().unwrap().to_string(),
-        );
+    values
+        .entry(annotations::ORG_OPENCONTAINERS_IMAGE_TITLE.to_string())
+        .or_insert_with(|| {
+            let name = config
+                .module
+                .file_name()
+                .unwrap_or(config.module.as_os_str());
+            name.to_string_lossy().into_owned()
+        });
+    if let Some(path) = &config.lockfile {
+        let lockfile = Lockfile::load(path).map_err(|source| PublishError::Lockfile {
+            path: path.clone(),
+            source,
+        })?;
+        values.insert(IMPORTS_ANNOTATION.to_string(), declare_imports(&lockfile));
     }
     let mut client = Client::new(oci_distribution::client::ClientConfig {
         protocol: oci_distribution::client::ClientProtocol::Https,
         ..Default::default()
     });
-    let reference: Reference = config.image.parse().expect("Not a valid image reference");
+    let reference: Reference =
+        config
+            .image
+            .parse()
+            .map_err(|source| PublishError::InvalidReference {
+                reference: config.image.clone(),
+                source,
+            })?;
     let auth = if let Some(username) = &config.username {
-        RegistryAuth::Basic(
-            username.clone(),
-            config.password.unwrap_or_default().clone(),
-        )
+        RegistryAuth::Basic(username.clone(), config.password.unwrap_or_default())
     } else {
         RegistryAuth::Anonymous
     };
-    push_wasm(&mut client, &auth, &reference, &config.module, Some(values)).await;
+    push_wasm(&mut client, &auth, &reference, &config.module, Some(values)).await
 }